@@ -1,28 +1,28 @@
-// #[macro_use]
-// extern crate clap;
+//! How to derive `ArgEnum` and use it with a hand-written `App`.
+use clap::{App, Arg, ArgEnum};
 
-// use clap::{App, Arg};
+#[derive(ArgEnum, Debug)]
+enum ArgChoice {
+    Foo,
+    Bar,
+    Baz,
+}
 
-fn main() {}
+fn main() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS),
+        )
+        .get_matches();
 
-// #[derive(ArgEnum, Debug)]
-// enum ArgChoice {
-//     Foo,
-//     Bar,
-//     Baz,
-// }
+    let t = matches
+        .value_of("arg")
+        .unwrap()
+        .parse::<ArgChoice>()
+        .unwrap();
 
-// fn main() {
-//     let matches = App::new(env!("CARGO_PKG_NAME"))
-//         .arg(
-//             Arg::with_name("arg")
-//                 .required(true)
-//                 .takes_value(true)
-//                 .possible_values(&ArgChoice::variants()),
-//         )
-//         .get_matches();
-
-//     let t = value_t!(matches.value_of("arg"), ArgChoice).unwrap_or_else(|e| e.exit());
-
-//     println!("{:?}", t);
-// }
+    println!("{:?}", t);
+}
@@ -0,0 +1,30 @@
+//! How to make an `ArgEnum` accept its values in any case.
+use clap::{App, Arg, ArgEnum};
+
+#[derive(ArgEnum, Debug)]
+#[clap(case_insensitive = true)]
+enum ArgChoice {
+    Foo,
+    Bar,
+    Baz,
+}
+
+fn main() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS)
+                .case_insensitive(ArgChoice::CASE_INSENSITIVE),
+        )
+        .get_matches();
+
+    let t = matches
+        .value_of("arg")
+        .unwrap()
+        .parse::<ArgChoice>()
+        .unwrap();
+
+    println!("{:?}", t);
+}
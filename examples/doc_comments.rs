@@ -19,18 +19,16 @@ struct Opt {
     /// `long_help` argument.
     ///
     /// But if the doc comment is formatted like this example
-    /// -- with an empty second line splitting the heading and
-    /// the rest of the comment -- only the first line is used
+    /// -- with an empty line splitting the first paragraph and
+    /// the rest of the comment -- only the first paragraph is used
     /// as `help` argument. The `long_help` argument will still
     /// contain the whole comment.
     ///
-    /// ## Attention
+    /// The markdown of the doc comment is turned into plain text:
     ///
-    /// Any formatting next to empty lines that could be used
-    /// inside a doc comment is currently not preserved. If
-    /// lists or other well formatted content is required it is
-    /// necessary to use the related clap argument with a
-    /// raw string as shown on the `third_flag` description.
+    /// - lists are kept,
+    /// - `code` and **emphasis** lose their markers,
+    /// - [links](https://docs.rs/clap) are followed by their url.
     #[clap(short, long)]
     second_flag: bool,
 
@@ -48,6 +46,14 @@ code) in the description:
     )]
     third_flag: bool,
 
+    /// Doc comments can be used as they are written too,
+    /// with `verbatim_doc_comment`:
+    ///
+    ///   first   the first column
+    ///   second  the second column
+    #[clap(long, verbatim_doc_comment)]
+    fourth_flag: bool,
+
     #[clap(subcommand)]
     sub_command: SubCommand,
 }
@@ -65,6 +71,15 @@ enum SubCommand {
     /// `second_flag` also apply to the description of
     /// sub-commands which is normally given through the `about`
     /// and `long_about` arguments.
+    ///
+    /// # Examples
+    ///
+    /// The sections of the doc comment of a command are shown
+    /// after the long help, formatted like its own sections:
+    ///
+    /// ```text
+    /// $ basic second --help
+    /// ```
     Second,
 }
 
@@ -1,25 +1,23 @@
-//! How to use `arg_enum!` with `StructOpt`.
-// TODO: make it work
-fn main() {}
-// use clap::Clap;
+//! How to use `#[derive(ArgEnum)]` with `clap_derive`.
+use clap::{ArgEnum, Clap};
 
-// arg_enum! {
-//     #[derive(Debug)]
-//     enum Baz {
-//         Foo,
-//         Bar,
-//         FooBar
-//     }
-// }
+#[derive(ArgEnum, Debug)]
+#[clap(rename_all = "kebab", case_insensitive = true)]
+enum Baz {
+    Foo,
+    Bar,
+    FooBar,
+}
 
-// #[derive(Clap, Debug)]
-// struct Opt {
-//     /// Important argument.
-//     #[clap(possible_values = &Baz::variants(), case_insensitive = true)]
-//     i: Baz,
-// }
+#[derive(Clap, Debug)]
+struct Opt {
+    /// Important argument.
+    ///
+    /// The accepted values are listed in the help message.
+    i: Baz,
+}
 
-// fn main() {
-//     let opt = Opt::parse();
-//     println!("{:?}", opt);
-// }
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
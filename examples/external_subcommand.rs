@@ -0,0 +1,24 @@
+//! How to forward unknown subcommands to external programs, the way
+//! `git foo` runs `git-foo` from the `$PATH`.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(name = "cargo")]
+enum Opt {
+    /// Compile the current package
+    Build {
+        #[clap(long)]
+        release: bool,
+    },
+    /// Anything else is run as `cargo-<name> <args>...`
+    #[clap(external_subcommand)]
+    Other(Vec<String>),
+}
+
+fn main() {
+    match Opt::parse() {
+        Opt::Build { release } => println!("building, release: {}", release),
+        Opt::Other(args) => println!("running cargo-{} with {:?}", args[0], &args[1..]),
+    }
+}
@@ -1,4 +1,5 @@
-//! How to use flattening.
+//! How to use flattening, with the doc comment as the heading of the
+//! flattened arguments thanks to a bare `help_heading`.
 
 use clap::Clap;
 
@@ -8,7 +9,8 @@ struct Cmdline {
     #[clap(short)]
     verbose: bool,
 
-    #[clap(flatten)]
+    /// Daemon options
+    #[clap(flatten, help_heading)]
     daemon_opts: DaemonOpts,
 }
 
@@ -7,88 +7,204 @@
 // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
-use proc_macro2;
-// use quote;
-use syn;
-// use syn::punctuated;
-// use syn::token;
-
-pub fn derive_arg_enum(_ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    unimplemented!()
-
-    // let from_str_block = impl_from_str(ast)?;
-    // let variants_block = impl_variants(ast)?;
-
-    // quote! {
-    //     #from_str_block
-    //     #variants_block
-    // }
-}
+use proc_macro2::{self, Span};
+use proc_macro_error::{abort_call_site, emit_error, set_dummy};
+use quote::quote;
+use syn::{self, punctuated, spanned::Spanned, token};
 
-/*
-fn impl_from_str(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    let ident = &ast.ident;
-    let is_case_sensitive = ast.attrs.iter().any(|v| v.name() == "case_sensitive");
-    let variants = variants(ast)?;
+use super::{crate_path, spanned::Sp, with_crate_path, Attrs, CasingStyle, Name};
 
-    let strings = variants
-        .iter()
-        .map(|ref variant| String::from(variant.ident.as_ref()))
-        .collect::<Vec<_>>();
+/// Default casing style for the values of an `ArgEnum`.
+///
+/// Variants are matched by their name as written in the code, unless
+/// `rename_all` says otherwise.
+const DEFAULT_VALUE_CASING: CasingStyle = CasingStyle::Verbatim;
 
-    // All of these need to be iterators.
-    let ident_slice = [ident.clone()];
-    let idents = ident_slice.iter().cycle();
+pub fn derive_arg_enum(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    use syn::Data::*;
 
-    let for_error_message = strings.clone();
+    let name = &input.ident;
+    let crate_path = crate_path(&input.attrs);
 
-    let condition_function_slice = [match is_case_sensitive {
-        true => quote! { str::eq },
-        false => quote! { ::std::ascii::AsciiExt::eq_ignore_ascii_case },
-    }];
-    let condition_function = condition_function_slice.iter().cycle();
+    let dummy = quote! {
+        impl ::clap::ArgEnum for #name {
+            const VARIANTS: &'static [&'static str] = &[];
+            const CASE_INSENSITIVE: bool = false;
+        }
 
-    Ok(quote! {
-        impl ::std::str::FromStr for #ident {
+        impl ::std::str::FromStr for #name {
             type Err = String;
 
             fn from_str(input: &str) -> ::std::result::Result<Self, Self::Err> {
-                match input {
-                    #(val if #condition_function(val, #strings) => Ok(#idents::#variants),)*
-                    _ => Err({
-                        let v = #for_error_message;
-                        format!("valid values: {}",
-                            v.join(" ,"))
-                    }),
-                }
+                unimplemented!()
             }
         }
-    })
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                unimplemented!()
+            }
+        }
+    };
+    set_dummy(with_crate_path(dummy, crate_path.as_ref()));
+
+    let tokens = match input.data {
+        Enum(ref e) => gen_for_enum(name, &input.attrs, &e.variants),
+        _ => abort_call_site!("`#[derive(ArgEnum)]` only supports enums"),
+    };
+    with_crate_path(tokens, crate_path.as_ref())
+}
+
+/// A variant of the enum with the values it can be parsed from.
+struct Value {
+    variant: syn::Ident,
+    name: syn::LitStr,
+    aliases: Vec<proc_macro2::TokenStream>,
 }
 
-fn impl_variants(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    let ident = &ast.ident;
-    let variants = variants(ast)?
+fn gen_for_enum(
+    name: &syn::Ident,
+    attrs: &[syn::Attribute],
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+) -> proc_macro2::TokenStream {
+    let attrs = Attrs::from_struct(
+        Span::call_site(),
+        attrs,
+        Name::Derived(name.clone()),
+        Sp::call_site(DEFAULT_VALUE_CASING),
+    );
+    let case_insensitive = attrs
+        .find_method("case_insensitive")
+        .map_or_else(|| quote!(false), |m| m.args().clone());
+
+    let values = gen_values(variants, &attrs);
+    let arg_enum = gen_arg_enum(name, &values, &case_insensitive);
+    let from_str = gen_from_str(name, &values);
+    let display = gen_display(name, &values);
+
+    quote! {
+        #arg_enum
+
+        #from_str
+
+        #display
+    }
+}
+
+fn gen_values(
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> Vec<Value> {
+    variants
         .iter()
-        .map(|ref variant| String::from(variant.ident.as_ref()))
-        .collect::<Vec<_>>();
-    let length = variants.len();
-
-    Ok(quote! {
-        impl #ident {
-            fn variants() -> [&'static str; #length] {
-                #variants
+        .map(|variant| {
+            if let syn::Fields::Unit = variant.fields {
+            } else {
+                emit_error!(
+                    variant.fields.span(),
+                    "`#[derive(ArgEnum)]` only supports unit variants"
+                );
+            }
+
+            let attrs = Attrs::from_struct(
+                variant.span(),
+                &variant.attrs,
+                Name::Derived(variant.ident.clone()),
+                parent_attribute.casing(),
+            );
+            let methods = attrs
+                .methods()
+                .iter()
+                .filter(|m| m.name() != "about" && m.name() != "long_about");
+            if let Some(m) = methods.clone().find(|m| m.name() != "alias") {
+                emit_error!(
+                    m.name().span(),
+                    "`{}` is not supported on `ArgEnum` variants",
+                    m.name();
+                    help = "only `name` and `alias` can be used here"
+                );
+            }
+
+            Value {
+                variant: variant.ident.clone(),
+                name: attrs.cased_name(),
+                aliases: methods.map(|m| m.args().clone()).collect(),
             }
+        })
+        .collect()
+}
+
+fn gen_arg_enum(
+    name: &syn::Ident,
+    values: &[Value],
+    case_insensitive: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let names = values.iter().map(|v| &v.name);
+
+    quote! {
+        impl ::clap::ArgEnum for #name {
+            const VARIANTS: &'static [&'static str] = &[#( #names ),*];
+            const CASE_INSENSITIVE: bool = #case_insensitive;
         }
-    })
+    }
 }
 
-fn variants(ast: &syn::DeriveInput) -> &punctuated::Punctuated<syn::Variant, token::Comma> {
-    use syn::Data::*;
+fn gen_from_str(name: &syn::Ident, values: &[Value]) -> proc_macro2::TokenStream {
+    let match_arms = values.iter().map(|value| {
+        let Value {
+            variant,
+            name: value_name,
+            aliases,
+        } = value;
+
+        quote! {
+            s if eq(s, #value_name) #( || eq(s, #aliases) )* => Ok(#name::#variant),
+        }
+    });
+
+    quote! {
+        impl ::std::str::FromStr for #name {
+            type Err = String;
 
-    match ast.data {
-        Enum(ref data) => data.variants,
-        _ => panic!("Only enums are supported for deriving the ArgEnum trait"),
+            fn from_str(input: &str) -> ::std::result::Result<Self, Self::Err> {
+                let eq = |s: &str, value: &str| {
+                    if <#name as ::clap::ArgEnum>::CASE_INSENSITIVE {
+                        s.eq_ignore_ascii_case(value)
+                    } else {
+                        s == value
+                    }
+                };
+
+                match input {
+                    #( #match_arms )*
+                    _ => Err(format!(
+                        "valid values: {}",
+                        <#name as ::clap::ArgEnum>::VARIANTS.join(", ")
+                    )),
+                }
+            }
+        }
+    }
+}
+
+fn gen_display(name: &syn::Ident, values: &[Value]) -> proc_macro2::TokenStream {
+    let match_arms = values.iter().map(|value| {
+        let Value {
+            variant,
+            name: value_name,
+            ..
+        } = value;
+
+        quote!(#name::#variant => f.write_str(#value_name),)
+    });
+
+    quote! {
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match *self {
+                    #( #match_arms )*
+                }
+            }
+        }
     }
 }
-*/
@@ -12,15 +12,23 @@
 // commit#ea76fa1b1b273e65e3b0b1046643715b49bec51f which is licensed under the
 // MIT/Apache 2.0 license.
 
-use super::{parse::*, spanned::Sp, ty::Ty};
+use super::{
+    doc_comments::{render_doc_comment, verbatim_doc_comment},
+    parse::*,
+    spanned::Sp,
+    ty::{is_simple_ty, sub_type, Ty},
+};
 
 use std::env;
 
 use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{self, Span, TokenStream};
-use proc_macro_error::abort;
+use proc_macro_error::emit_error;
 use quote::{quote, quote_spanned, ToTokens};
-use syn::{self, ext::IdentExt, spanned::Spanned, Attribute, Expr, Ident, LitStr, MetaNameValue};
+use syn::{
+    self, ext::IdentExt, punctuated::Punctuated, spanned::Spanned, token, Attribute, Expr, Ident,
+    LitStr, MetaNameValue,
+};
 
 /// Default casing style for generated arguments.
 pub const DEFAULT_CASING: CasingStyle = CasingStyle::Kebab;
@@ -29,7 +37,8 @@ pub const DEFAULT_CASING: CasingStyle = CasingStyle::Kebab;
 pub enum Kind {
     Arg(Sp<Ty>),
     Subcommand(Sp<Ty>),
-    FlattenStruct,
+    ExternalSubcommand,
+    FlattenStruct(Sp<Ty>),
     Skip(Option<syn::Expr>),
 }
 
@@ -37,6 +46,7 @@ pub enum Kind {
 pub struct Method {
     name: syn::Ident,
     args: proc_macro2::TokenStream,
+    arity: usize,
 }
 
 #[derive(Clone)]
@@ -88,7 +98,21 @@ pub struct Attrs {
     about: Option<Method>,
     version: Option<Method>,
     no_version: Option<syn::Ident>,
+    prefix: Option<(syn::Ident, syn::LitStr)>,
+    repeated: Option<syn::Ident>,
+    negatable: Option<syn::Ident>,
+    default_value_t: Option<(syn::Ident, Option<syn::Expr>)>,
+    env_prefix: Option<(syn::Ident, syn::LitStr)>,
+    env_names: Option<(syn::Ident, Vec<syn::Expr>)>,
+    no_env: Option<syn::Ident>,
+    verbatim_doc_comment: Option<syn::Ident>,
+    help_heading: Option<LitStr>,
+    doc_heading: Option<syn::Ident>,
+    derive_display_order: Option<syn::Ident>,
+    env_only: Option<syn::Ident>,
+    crate_path: Option<syn::Ident>,
     has_custom_parser: bool,
+    has_invalid_attrs: bool,
     kind: Sp<Kind>,
 }
 
@@ -104,7 +128,11 @@ pub struct GenOutput {
 
 impl Method {
     fn new(name: syn::Ident, args: proc_macro2::TokenStream) -> Self {
-        Method { name, args }
+        Method {
+            name,
+            args,
+            arity: 1,
+        }
     }
 
     fn from_lit_or_env(ident: syn::Ident, lit: Option<syn::LitStr>, env_var: &str) -> Option<Self> {
@@ -114,11 +142,12 @@ impl Method {
             None => match env::var(env_var) {
                 Ok(val) => syn::LitStr::new(&val, ident.span()),
                 Err(_) => {
-                    abort!(ident.span(),
+                    emit_error!(ident.span(),
                         "cannot derive `{}` from Cargo.toml", ident;
                         note = "`{}` environment variable is not set", env_var;
                         help = "use `{} = \"...\"` to set {} manually", ident, ident;
                     );
+                    return None;
                 }
             },
         };
@@ -130,11 +159,26 @@ impl Method {
 
         Some(Method::new(ident, quote!(#lit)))
     }
+
+    pub fn name(&self) -> &syn::Ident {
+        &self.name
+    }
+
+    pub fn args(&self) -> &proc_macro2::TokenStream {
+        &self.args
+    }
+
+    /// The number of arguments given to the method.
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
 }
 
 impl ToTokens for Method {
     fn to_tokens(&self, ts: &mut proc_macro2::TokenStream) {
-        let Method { ref name, ref args } = self;
+        let Method {
+            ref name, ref args, ..
+        } = self;
 
         let tokens = if name == "short" {
             quote!( .#name(#args.chars().nth(0).unwrap()) )
@@ -163,7 +207,10 @@ impl Parser {
             "try_from_os_str" => TryFromOsStr,
             "from_occurrences" => FromOccurrences,
             "from_flag" => FromFlag,
-            s => abort!(spec.kind.span(), "unsupported parser `{}`", s),
+            s => {
+                emit_error!(spec.kind.span(), "unsupported parser `{}`", s);
+                TryFromStr
+            }
         };
 
         let func = match spec.parse_func {
@@ -172,17 +219,23 @@ impl Parser {
                     quote_spanned!(spec.kind.span()=> ::std::convert::From::from)
                 }
                 TryFromStr => quote_spanned!(spec.kind.span()=> ::std::str::FromStr::from_str),
-                TryFromOsStr => abort!(
-                    spec.kind.span(),
-                    "you must set parser for `try_from_os_str` explicitly"
-                ),
+                TryFromOsStr => {
+                    emit_error!(
+                        spec.kind.span(),
+                        "you must set parser for `try_from_os_str` explicitly"
+                    );
+                    quote!()
+                }
                 FromOccurrences => quote_spanned!(spec.kind.span()=> { |v| v as _ }),
                 FromFlag => quote_spanned!(spec.kind.span()=> ::std::convert::From::from),
             },
 
             Some(func) => match func {
                 syn::Expr::Path(_) => quote!(#func),
-                _ => abort!(func.span(), "`parse` argument must be a function path"),
+                _ => {
+                    emit_error!(func.span(), "`parse` argument must be a function path");
+                    quote!()
+                }
             },
         };
 
@@ -206,7 +259,10 @@ impl CasingStyle {
             "screamingsnake" | "screamingsnakecase" => cs(ScreamingSnake),
             "snake" | "snakecase" => cs(Snake),
             "verbatim" | "verbatimcase" => cs(Verbatim),
-            s => abort!(name.span(), "unsupported casing: `{}`", s),
+            s => {
+                emit_error!(name.span(), "unsupported casing: `{}`", s);
+                cs(Verbatim)
+            }
         }
     }
 }
@@ -244,8 +300,22 @@ impl Attrs {
             author: None,
             version: None,
             no_version: None,
+            prefix: None,
+            repeated: None,
+            negatable: None,
+            default_value_t: None,
+            env_prefix: None,
+            env_names: None,
+            no_env: None,
+            verbatim_doc_comment: None,
+            help_heading: None,
+            doc_heading: None,
+            derive_display_order: None,
+            env_only: None,
+            crate_path: None,
 
             has_custom_parser: false,
+            has_invalid_attrs: false,
             kind: Sp::new(Kind::Arg(Sp::new(Ty::Other, default_span)), default_span),
         }
     }
@@ -256,6 +326,9 @@ impl Attrs {
             ("name", _) => {
                 self.name = Name::Assigned(arg.as_lit());
             }
+            ("help_heading", _) => {
+                self.help_heading = Some(arg.as_lit());
+            }
             _ => self
                 .methods
                 .push(Method::new(name.as_ident(), quote!(#arg))),
@@ -265,7 +338,9 @@ impl Attrs {
     fn push_attrs(&mut self, attrs: &[syn::Attribute]) {
         use ClapAttr::*;
 
-        for attr in parse_clap_attributes(attrs) {
+        let (attrs, invalid) = parse_clap_attributes(attrs);
+        self.has_invalid_attrs |= invalid;
+        for attr in attrs {
             match attr {
                 Short(ident) | Long(ident) => {
                     self.push_str_method(
@@ -280,11 +355,43 @@ impl Attrs {
                     self.set_kind(kind);
                 }
 
+                ExternalSubcommand(ident) => {
+                    let kind = Sp::new(Kind::ExternalSubcommand, ident.span());
+                    self.set_kind(kind);
+                }
+
                 Flatten(ident) => {
-                    let kind = Sp::new(Kind::FlattenStruct, ident.span());
+                    let ty = Sp::call_site(Ty::Other);
+                    let kind = Sp::new(Kind::FlattenStruct(ty), ident.span());
                     self.set_kind(kind);
                 }
 
+                Repeated(ident) => self.repeated = Some(ident),
+
+                Negatable(ident) => self.negatable = Some(ident),
+
+                DefaultValueT(ident, expr) => self.default_value_t = Some((ident, expr)),
+
+                EnvPrefix(ident, prefix) => self.env_prefix = Some((ident, prefix)),
+
+                Env(ident, names) => self.env_names = Some((ident, names)),
+
+                NoEnv(ident) => self.no_env = Some(ident),
+
+                VerbatimDocComment(ident) => self.verbatim_doc_comment = Some(ident),
+
+                EnvOnly(ident) => self.env_only = Some(ident),
+
+                Crate(ident, path) => {
+                    if path.parse::<syn::Path>().is_err() {
+                        emit_error!(
+                            path.span(),
+                            "`crate` must be a path to the clap crate, like \"my_cli::clap\""
+                        );
+                    }
+                    self.crate_path = Some(ident);
+                }
+
                 Skip(ident, expr) => {
                     let kind = Sp::new(Kind::Skip(expr), ident.span());
                     self.set_kind(kind);
@@ -292,6 +399,10 @@ impl Attrs {
 
                 NoVersion(ident) => self.no_version = Some(ident),
 
+                DeriveDisplayOrder(ident) => self.derive_display_order = Some(ident),
+
+                HelpHeading(ident) => self.doc_heading = Some(ident),
+
                 About(ident, about) => {
                     self.about = Method::from_lit_or_env(ident, about, "CARGO_PKG_DESCRIPTION");
                 }
@@ -310,7 +421,12 @@ impl Attrs {
 
                 NameExpr(name, expr) => self.methods.push(Method::new(name, quote!(#expr))),
 
-                MethodCall(name, args) => self.methods.push(Method::new(name, quote!(#(#args),*))),
+                MethodCall(name, args) => self.methods.push(Method {
+                    arity: args.len(),
+                    ..Method::new(name, quote!(#(#args),*))
+                }),
+
+                Prefix(ident, prefix) => self.prefix = Some((ident, prefix)),
 
                 RenameAll(_, casing_lit) => {
                     self.casing = CasingStyle::from_lit(casing_lit);
@@ -344,66 +460,39 @@ impl Attrs {
                     if !path.is_ident("doc") {
                         return None;
                     }
-                    let value = s.value();
-
-                    let text = value
-                        .trim_start_matches("//!")
-                        .trim_start_matches("///")
-                        .trim_start_matches("/*!")
-                        .trim_start_matches("/**")
-                        .trim_end_matches("*/")
-                        .trim();
-                    if text.is_empty() {
-                        Some("\n\n".to_string())
-                    } else {
-                        Some(text.to_string())
-                    }
+                    Some(s.value())
                 } else {
                     None
                 }
             })
             .collect::<Vec<_>>();
-        if doc_comments.is_empty() {
-            return;
-        }
-        let merged_lines = doc_comments
-            .join(" ")
-            .split('\n')
-            .map(str::trim)
-            .map(str::to_string)
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        let expected_doc_comment_split = if let Some(content) = doc_comments.get(1) {
-            (doc_comments.len() > 2) && (content == &"\n\n")
+
+        // only an app has room for sections after its help
+        let sections = name == "about";
+        let help = if self.verbatim_doc_comment.is_some() {
+            verbatim_doc_comment(&doc_comments)
         } else {
-            false
+            render_doc_comment(&doc_comments, sections)
+        };
+        let help = match help {
+            Some(help) => help,
+            None => return,
         };
 
-        if expected_doc_comment_split {
+        // first, so that the explicit methods override them
+        let short = help
+            .short
+            .map(|short| Method::new(syn::Ident::new(name, Span::call_site()), quote!(#short)));
+        let long = help.long.map(|long| {
             let long_name = Sp::call_site(format!("long_{}", name));
-
-            self.methods
-                .push(Method::new(long_name.as_ident(), quote!(#merged_lines)));
-
-            // Remove trailing whitespace and period from short help, as rustdoc
-            // best practice is to use complete sentences, but command-line help
-            // typically omits the trailing period.
-            let short_arg = doc_comments
-                .first()
-                .map(|s| s.trim())
-                .map_or("", |s| s.trim_end_matches('.'));
-
-            self.methods.push(Method::new(
-                syn::Ident::new(name, Span::call_site()),
-                quote!(#short_arg),
-            ));
-        } else {
-            self.methods.push(Method::new(
-                syn::Ident::new(name, Span::call_site()),
-                quote!(#merged_lines),
-            ));
-        }
+            Method::new(long_name.as_ident(), quote!(#long))
+        });
+        let sections = help.sections.map(|sections| {
+            let name = syn::Ident::new("after_long_help", Span::call_site());
+            Method::new(name, quote!(#sections))
+        });
+        self.methods
+            .splice(0..0, long.into_iter().chain(short).chain(sections));
     }
 
     pub fn from_struct(
@@ -417,49 +506,216 @@ impl Attrs {
         res.push_doc_comment(attrs, "about");
 
         if res.has_custom_parser {
-            abort!(
+            emit_error!(
                 res.parser.span(),
                 "`parse` attribute is only allowed on fields"
             );
         }
         match &*res.kind {
-            Kind::Subcommand(_) => abort!(res.kind.span(), "subcommand is only allowed on fields"),
-            Kind::FlattenStruct => abort!(res.kind.span(), "flatten is only allowed on fields"),
-            Kind::Skip(_) => abort!(res.kind.span(), "skip is only allowed on fields"),
-            Kind::Arg(_) => res,
+            Kind::Subcommand(_) => {
+                emit_error!(res.kind.span(), "subcommand is only allowed on fields")
+            }
+            Kind::FlattenStruct(_) => {
+                emit_error!(res.kind.span(), "flatten is only allowed on fields")
+            }
+            Kind::Skip(_) => emit_error!(res.kind.span(), "skip is only allowed on fields"),
+            Kind::Arg(_) | Kind::ExternalSubcommand => (),
+        }
+        if let Some((ref ident, _)) = res.prefix {
+            emit_error!(ident.span(), "prefix is only allowed on flattened fields");
+        }
+        if let Some(ref ident) = res.repeated {
+            emit_error!(ident.span(), "repeated is only allowed on flattened fields");
+        }
+        if let Some(ref ident) = res.doc_heading {
+            emit_error!(
+                ident.span(),
+                "help_heading without a value is only allowed on flattened fields";
+                help = "give the heading as a value, like `help_heading = \"Options\"`"
+            );
+        }
+        if let Some(ref ident) = res.negatable {
+            emit_error!(ident.span(), "negatable is only allowed on fields");
         }
+        if let Some((ref ident, _)) = res.default_value_t {
+            emit_error!(ident.span(), "default_value_t is only allowed on fields");
+        }
+        if let Some((ref ident, _)) = res.env_names {
+            emit_error!(ident.span(), "env is only allowed on fields");
+        }
+        if let Some(ref ident) = res.no_env {
+            emit_error!(ident.span(), "no_env is only allowed on fields");
+        }
+        if let Some(ref ident) = res.env_only {
+            emit_error!(ident.span(), "env_only is only allowed on fields");
+        }
+        if let (Some(ref no_version), Some(_)) = (&res.no_version, &res.version) {
+            emit_error!(
+                no_version.span(),
+                "`no_version` and `version = \"version\"` can't be used together"
+            );
+        }
+
+        res
     }
 
     pub fn from_field(field: &syn::Field, struct_casing: Sp<CasingStyle>) -> Self {
         let name = field.ident.clone().unwrap();
         let mut res = Self::new(field.span(), Name::Derived(name.clone()), struct_casing);
-        res.push_doc_comment(&field.attrs, "help");
         res.push_attrs(&field.attrs);
+        res.push_doc_comment(&field.attrs, "help");
+
+        // the errors following from an invalid attribute would be misleading
+        if res.has_invalid_attrs {
+            return res;
+        }
+
+        if let Some((ref ident, _)) = res.prefix {
+            match *res.kind {
+                Kind::FlattenStruct(_) => (),
+                _ => emit_error!(ident.span(), "prefix is only allowed on flattened fields"),
+            }
+        }
+        if let Some(ref ident) = res.repeated {
+            match *res.kind {
+                Kind::FlattenStruct(_) => (),
+                _ => emit_error!(ident.span(), "repeated is only allowed on flattened fields"),
+            }
+        }
+        if let Some(ref ident) = res.doc_heading {
+            match *res.kind {
+                Kind::FlattenStruct(_) => (),
+                _ => emit_error!(
+                    ident.span(),
+                    "help_heading without a value is only allowed on flattened fields";
+                    help = "give the heading as a value, like `help_heading = \"Options\"`"
+                ),
+            }
+        }
+        if let Some(ref ident) = res.negatable {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => emit_error!(
+                    ident.span(),
+                    "negatable is only allowed on bool and Option<bool> fields"
+                ),
+            }
+        }
+        if let Some((ref ident, _)) = res.default_value_t {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => emit_error!(ident.span(), "default_value_t is only allowed on arguments"),
+            }
+        }
+        if let Some((ref ident, _)) = res.env_prefix {
+            emit_error!(
+                ident.span(),
+                "env_prefix is only allowed on structs, enums and variants"
+            );
+        }
+        if let Some(ref ident) = res.crate_path {
+            emit_error!(ident.span(), "crate is only allowed on structs and enums");
+        }
+        if let Some(ref ident) = res.derive_display_order {
+            emit_error!(
+                ident.span(),
+                "derive_display_order is only allowed on structs, enums and variants"
+            );
+        }
+        let env_attrs = vec![
+            res.env_names.as_ref().map(|(ident, _)| ident),
+            res.no_env.as_ref(),
+            res.env_only.as_ref(),
+        ];
+        for ident in env_attrs.into_iter().flatten() {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => emit_error!(ident.span(), "{} is only allowed on arguments", ident),
+            }
+        }
 
         match &*res.kind {
-            Kind::FlattenStruct => {
+            Kind::ExternalSubcommand => emit_error!(
+                res.kind.span(),
+                "external_subcommand is only allowed on enum variants"
+            ),
+            Kind::FlattenStruct(_) => {
                 if res.has_custom_parser {
-                    abort!(
+                    emit_error!(
                         res.parser.span(),
                         "parse attribute is not allowed for flattened entry"
                     );
                 }
-                if res.has_explicit_methods() || res.has_doc_methods() {
-                    abort!(
+                if res.has_explicit_methods() {
+                    emit_error!(
                         res.kind.span(),
-                        "methods and doc comments are not allowed for flattened entry"
+                        "methods are not allowed for flattened entry"
                     );
                 }
+                // the doc comment is the heading of the flattened arguments
+                // when asked for with a bare `help_heading`, and is ignored
+                // otherwise
+                if let Some(ref ident) = res.doc_heading {
+                    let doc_heading = res
+                        .find_method("help")
+                        .and_then(|help| syn::parse2::<LitStr>(help.args().clone()).ok())
+                        .map(|help| {
+                            let heading = help.value();
+                            LitStr::new(heading.trim_end_matches('.'), help.span())
+                        });
+                    match (&res.help_heading, doc_heading) {
+                        (Some(_), _) => emit_error!(
+                            ident.span(),
+                            "help_heading is given both with and without a value"
+                        ),
+                        (None, None) => emit_error!(
+                            ident.span(),
+                            "help_heading without a value takes the doc comment of the field, \
+                             which has none";
+                            help = "add a doc comment, or give the heading as a value"
+                        ),
+                        (None, doc_heading) => res.help_heading = doc_heading,
+                    }
+                }
+                res.methods
+                    .retain(|m| m.name != "help" && m.name != "long_help");
+
+                let ty = Ty::from_syn_ty(&field.ty);
+                let ty = match (*ty, &res.repeated) {
+                    (Ty::Vec, Some(_)) => ty,
+                    (_, Some(repeated)) => {
+                        emit_error!(repeated.span(), "repeated flattened entry must be a Vec<T>");
+                        Sp::new(Ty::Other, ty.span())
+                    }
+                    (Ty::Vec, None) => {
+                        emit_error!(
+                            ty.span(),
+                            "Vec<T> flattened entry must be marked as `repeated`"
+                        );
+                        Sp::new(Ty::Other, ty.span())
+                    }
+                    (Ty::Option, None) => ty,
+                    (Ty::OptionOption, None) | (Ty::OptionVec, None) => {
+                        emit_error!(
+                            ty.span(),
+                            "only Option<T> and T types are allowed for flattened entry"
+                        );
+                        Sp::new(Ty::Other, ty.span())
+                    }
+                    _ => Sp::new(Ty::Other, ty.span()),
+                };
+
+                res.kind = Sp::new(Kind::FlattenStruct(ty), res.kind.span());
             }
             Kind::Subcommand(_) => {
                 if res.has_custom_parser {
-                    abort!(
+                    emit_error!(
                         res.parser.span(),
                         "parse attribute is not allowed for subcommand"
                     );
                 }
                 if res.has_explicit_methods() {
-                    abort!(
+                    emit_error!(
                         res.kind.span(),
                         "methods in attributes are not allowed for subcommand"
                     );
@@ -468,13 +724,13 @@ impl Attrs {
                 let ty = Ty::from_syn_ty(&field.ty);
                 match *ty {
                     Ty::OptionOption => {
-                        abort!(
+                        emit_error!(
                             ty.span(),
                             "Option<Option<T>> type is not allowed for subcommand"
                         );
                     }
                     Ty::OptionVec => {
-                        abort!(
+                        emit_error!(
                             ty.span(),
                             "Option<Vec<T>> type is not allowed for subcommand"
                         );
@@ -486,7 +742,7 @@ impl Attrs {
             }
             Kind::Skip(_) => {
                 if res.has_explicit_methods() {
-                    abort!(
+                    emit_error!(
                         res.kind.span(),
                         "methods are not allowed for skipped fields"
                     );
@@ -501,10 +757,31 @@ impl Attrs {
                     }
                 }
 
+                if let Some(ref negatable) = res.negatable {
+                    let is_bool = match *ty {
+                        Ty::Bool => true,
+                        Ty::Option => {
+                            sub_type(&field.ty).map(|sub_ty| is_simple_ty(sub_ty, "bool"))
+                                == Some(true)
+                        }
+                        _ => false,
+                    };
+                    if !is_bool || res.has_custom_parser {
+                        emit_error!(
+                            negatable.span(),
+                            "negatable is only allowed on bool and Option<bool> fields \
+                             with the default parser"
+                        );
+                    }
+                    if !res.has_method("long") {
+                        emit_error!(negatable.span(), "negatable flags must have a `long` name");
+                    }
+                }
+
                 match *ty {
                     Ty::Bool => {
                         if res.is_positional() && !res.has_custom_parser {
-                            abort!(ty.span(),
+                            emit_error!(ty.span(),
                                 "`bool` cannot be used as positional parameter with default parser";
                                 help = "if you want to create a flag add `long` or `short`";
                                 help = "If you really want a boolean parameter \
@@ -513,39 +790,113 @@ impl Attrs {
                             )
                         }
                         if let Some(m) = res.find_method("default_value") {
-                            abort!(m.name.span(), "default_value is meaningless for bool")
+                            emit_error!(m.name.span(), "default_value is meaningless for bool")
                         }
                         if let Some(m) = res.find_method("required") {
-                            abort!(m.name.span(), "required is meaningless for bool")
+                            emit_error!(m.name.span(), "required is meaningless for bool")
                         }
                     }
                     Ty::Option => {
                         if let Some(m) = res.find_method("default_value") {
-                            abort!(m.name.span(), "default_value is meaningless for Option")
+                            emit_error!(m.name.span(), "default_value is meaningless for Option")
                         }
                         if let Some(m) = res.find_method("required") {
-                            abort!(m.name.span(), "required is meaningless for Option")
+                            emit_error!(m.name.span(), "required is meaningless for Option")
                         }
                     }
-                    Ty::OptionOption => {
-                        if res.is_positional() {
-                            abort!(
-                                ty.span(),
-                                "Option<Option<T>> type is meaningless for positional argument"
+                    Ty::OptionOption if res.is_positional() => emit_error!(
+                        ty.span(),
+                        "Option<Option<T>> type is meaningless for positional argument"
+                    ),
+                    Ty::OptionVec if res.is_positional() => emit_error!(
+                        ty.span(),
+                        "Option<Vec<T>> type is meaningless for positional argument"
+                    ),
+
+                    _ => (),
+                }
+
+                if let Some(ref no_env) = res.no_env {
+                    if res.has_method("env") || res.env_names.is_some() {
+                        emit_error!(no_env.span(), "`no_env` and `env` can't be used together")
+                    }
+                    if let Some(ref env_only) = res.env_only {
+                        emit_error!(
+                            env_only.span(),
+                            "`no_env` and `env_only` can't be used together"
+                        )
+                    }
+                }
+                if let Some((ref ident, ref names)) = res.env_names {
+                    if names.is_empty() {
+                        emit_error!(ident.span(), "env needs at least one name")
+                    }
+                    if let Some(m) = res.find_method("env") {
+                        emit_error!(m.name.span(), "env can only be given once")
+                    }
+                }
+                if let Some(ref env_only) = res.env_only {
+                    if let Some(m) = res
+                        .methods
+                        .iter()
+                        .find(|m| m.name == "short" || m.name == "long" || m.name == "index")
+                    {
+                        emit_error!(
+                            m.name.span(),
+                            "env_only fields are not given on the command line, \
+                             they can't have a `{}`",
+                            m.name
+                        )
+                    }
+                    match (&*ty, &*res.parser.kind) {
+                        (_, ParserKind::FromOccurrences) | (_, ParserKind::FromFlag) => {
+                            emit_error!(
+                                res.parser.kind.span(),
+                                "env_only fields can't use the `from_occurrences` \
+                             and `from_flag` parsers"
                             )
                         }
+                        (Ty::Other, _) | (Ty::Option, _) => (),
+                        _ => emit_error!(
+                            env_only.span(),
+                            "env_only is only allowed on fields of type T or Option<T>"
+                        ),
                     }
-                    Ty::OptionVec => {
-                        if res.is_positional() {
-                            abort!(
-                                ty.span(),
-                                "Option<Vec<T>> type is meaningless for positional argument"
-                            )
+                }
+
+                if let Some((ref ident, _)) = res.default_value_t {
+                    if let Some(m) = res.find_method("default_value") {
+                        emit_error!(
+                            m.name.span(),
+                            "`default_value` and `default_value_t` can't be used together"
+                        )
+                    }
+                    match *ty {
+                        Ty::Other => (),
+                        Ty::Bool => {
+                            emit_error!(ident.span(), "default_value_t is meaningless for bool")
                         }
+                        _ => emit_error!(
+                            ident.span(),
+                            "default_value_t is only allowed on fields of type T, \
+                             not Option<T> or Vec<T>"
+                        ),
                     }
 
-                    _ => (),
+                    // rendered into the help through `Display`, and interned
+                    // as the app borrows it
+                    let value = res.default_value_t().unwrap();
+                    let field_ty = &field.ty;
+                    let rendered = quote_spanned! { ident.span()=>
+                        intern(::std::string::ToString::to_string(&{
+                            let value: #field_ty = #value;
+                            value
+                        }))
+                    };
+                    let name = syn::Ident::new("default_value", ident.span());
+                    res.methods.push(Method::new(name, rendered));
                 }
+
                 res.kind = Sp::new(Kind::Arg(ty), orig_ty.span());
             }
         }
@@ -557,7 +908,7 @@ impl Attrs {
         if let Kind::Arg(_) = *self.kind {
             self.kind = kind;
         } else {
-            abort!(
+            emit_error!(
                 kind.span(),
                 "subcommand, flatten and skip cannot be used together"
             );
@@ -572,21 +923,21 @@ impl Attrs {
         self.methods.iter().find(|m| m.name == name)
     }
 
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
     /// generate methods from attributes on top of struct or enum
     pub fn top_level_methods(&self) -> proc_macro2::TokenStream {
         let version = match (&self.no_version, &self.version) {
-            (Some(no_version), Some(_)) => abort!(
-                no_version.span(),
-                "`no_version` and `version = \"version\"` can't be used together"
-            ),
-
             (None, Some(m)) => m.to_token_stream(),
 
             (None, None) => std::env::var("CARGO_PKG_VERSION")
                 .map(|version| quote!( .version(#version) ))
                 .unwrap_or_default(),
 
-            (Some(_), None) => quote!(),
+            // both together are reported in `Attrs::from_struct`
+            (Some(_), _) => quote!(),
         };
 
         let author = &self.author;
@@ -614,10 +965,80 @@ impl Attrs {
         self.kind.clone()
     }
 
+    /// The heading of the arguments of a field, a flattened field, a struct
+    /// or a variant.
+    pub fn help_heading(&self) -> Option<&LitStr> {
+        self.help_heading.as_ref()
+    }
+
+    /// Whether the arguments and subcommands of a struct or variant, or the
+    /// subcommands of an enum and their arguments, are displayed in the order
+    /// of declaration.
+    pub fn derive_display_order(&self) -> bool {
+        self.derive_display_order.is_some()
+    }
+
+    /// The prefix of the arguments of a flattened struct, if any.
+    pub fn prefix(&self) -> Option<&syn::LitStr> {
+        self.prefix.as_ref().map(|(_, prefix)| prefix)
+    }
+
     pub fn casing(&self) -> Sp<CasingStyle> {
         self.casing.clone()
     }
 
+    pub fn has_custom_parser(&self) -> bool {
+        self.has_custom_parser
+    }
+
+    /// Whether some attributes were invalid, the errors being reported.
+    pub fn has_invalid_attrs(&self) -> bool {
+        self.has_invalid_attrs
+    }
+
+    /// The `env_prefix` of a struct, an enum or a variant.
+    pub fn env_prefix(&self) -> Option<&LitStr> {
+        self.env_prefix.as_ref().map(|(_, prefix)| prefix)
+    }
+
+    /// The environment variables given by `env = [...]`.
+    pub fn env_names(&self) -> Option<&[syn::Expr]> {
+        self.env_names.as_ref().map(|(_, names)| &**names)
+    }
+
+    /// The name of the environment variable bound to an argument by
+    /// `env_prefix`, without this prefix.
+    pub fn env_name(&self) -> LitStr {
+        let name = self.cased_name();
+        LitStr::new(&name.value().to_shouty_snake_case(), name.span())
+    }
+
+    pub fn has_no_env(&self) -> bool {
+        self.no_env.is_some()
+    }
+
+    pub fn is_env_only(&self) -> bool {
+        self.env_only.is_some()
+    }
+
+    /// The value given by `default_value_t`, `Default::default()` if omitted.
+    pub fn default_value_t(&self) -> Option<TokenStream> {
+        self.default_value_t
+            .as_ref()
+            .map(|(ident, expr)| match expr {
+                Some(expr) => quote_spanned!(ident.span()=> #expr),
+                None => quote_spanned!(ident.span()=> ::std::default::Default::default()),
+            })
+    }
+
+    /// The name of the `--no-<name>` companion of a negatable flag.
+    pub fn negated_name(&self) -> Option<LitStr> {
+        self.negatable.as_ref().map(|negatable| {
+            let name = format!("no-{}", self.cased_name().value());
+            LitStr::new(&name, negatable.span())
+        })
+    }
+
     pub fn is_positional(&self) -> bool {
         self.methods
             .iter()
@@ -629,11 +1050,30 @@ impl Attrs {
             .iter()
             .any(|m| m.name != "help" && m.name != "long_help")
     }
+}
 
-    pub fn has_doc_methods(&self) -> bool {
-        self.methods
+/// Get the fields of a struct or variant, naming the unnamed ones after
+/// `parent` and their position: the fields of `Copy(PathBuf, PathBuf)` are
+/// handled as if they were named `Copy_0` and `Copy_1`.
+pub fn named_fields(
+    fields: &syn::Fields,
+    parent: &syn::Ident,
+) -> Punctuated<syn::Field, token::Comma> {
+    match fields {
+        syn::Fields::Named(fields) => fields.named.clone(),
+        syn::Fields::Unnamed(fields) => fields
+            .unnamed
             .iter()
-            .any(|m| m.name == "help" || m.name == "long_help")
+            .enumerate()
+            .map(|(index, field)| {
+                let name = format!("{}_{}", parent.unraw(), index);
+                syn::Field {
+                    ident: Some(Ident::new(&name, field.span())),
+                    ..field.clone()
+                }
+            })
+            .collect(),
+        syn::Fields::Unit => Punctuated::new(),
     }
 }
 
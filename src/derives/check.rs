@@ -0,0 +1,295 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Attributes checking
+//!
+//! The code generation builds the `Attrs` of a field or a variant several
+//! times, so the errors are collected in a single pass over the whole input
+//! beforehand, each of them being reported once.
+
+use std::{env, fmt};
+
+use proc_macro2::Span;
+use proc_macro_error::{abort_if_dirty, emit_error};
+use syn::{self, punctuated, spanned::Spanned, token};
+
+use super::{
+    clap::find_external_subcommand,
+    methods::{closest, APP_METHODS, ARG_METHODS, FIELD_ATTRS, STRUCT_ATTRS},
+    named_fields,
+    spanned::Sp,
+    Attrs, Kind, Name, DEFAULT_CASING,
+};
+
+/// Check the attributes of `input`, its fields and its variants, aborting
+/// with all the errors found, if any.
+pub fn check_attrs(input: &syn::DeriveInput) {
+    use syn::Data::*;
+
+    let name = env::var("CARGO_PKG_NAME").ok().unwrap_or_default();
+    let attrs = Attrs::from_struct(
+        Span::call_site(),
+        &input.attrs,
+        Name::Assigned(syn::LitStr::new(&name, Span::call_site())),
+        Sp::call_site(DEFAULT_CASING),
+    );
+    if let Kind::ExternalSubcommand = *attrs.kind() {
+        emit_error!(
+            attrs.kind().span(),
+            "external_subcommand is only allowed on enum variants"
+        );
+    }
+    check_methods(&attrs, Target::App);
+
+    match input.data {
+        Struct(ref data) => check_fields(&data.fields, &input.ident, &attrs),
+        Enum(ref data) => check_variants(&data.variants, &attrs),
+        Union(_) => (),
+    }
+
+    abort_if_dirty();
+}
+
+fn check_fields(fields: &syn::Fields, name: &syn::Ident, parent_attribute: &Attrs) {
+    let mut subcommands = Vec::new();
+    let mut keys = Vec::new();
+    for field in named_fields(fields, name) {
+        let attrs = Attrs::from_field(&field, parent_attribute.casing());
+        if attrs.has_invalid_attrs() {
+            continue;
+        }
+        match *attrs.kind() {
+            Kind::Subcommand(_) => subcommands.push(field.span()),
+            Kind::Arg(_) => {
+                check_methods(&attrs, Target::Arg);
+                // read from the environment, never an argument
+                if !attrs.is_env_only() {
+                    let field_name = field.ident.unwrap();
+                    keys.extend(
+                        arg_keys(&attrs)
+                            .into_iter()
+                            .map(|(key, span)| (key, span, field_name.clone())),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(&span) = subcommands.get(1) {
+        emit_error!(
+            span,
+            "multiple subcommand sets are not allowed, that's the second"
+        );
+    }
+
+    // the first field using a key is reported along with the second one only
+    let mut reported = vec![false; keys.len()];
+    for (i, (key, span, field_name)) in keys.iter().enumerate() {
+        let first = keys[..i]
+            .iter()
+            .position(|(other, _, other_field)| other == key && other_field != field_name);
+        if let Some(first) = first {
+            let (_, first_span, first_name) = &keys[first];
+            for &(index, span) in &[(first, *first_span), (i, *span)] {
+                if reported[index] {
+                    continue;
+                }
+                reported[index] = true;
+                emit_error!(
+                    span,
+                    "{} is used by both `{}` and `{}`",
+                    key,
+                    first_name,
+                    field_name
+                );
+            }
+        }
+    }
+}
+
+fn check_variants(
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) {
+    use syn::Fields::*;
+
+    for variant in variants {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            parent_attribute.casing(),
+        );
+        if let Kind::Arg(_) = *attrs.kind() {
+            check_methods(&attrs, Target::App);
+        }
+        match variant.fields {
+            Unit => (),
+            // delegates to the inner type, which is checked by its own derive
+            Unnamed(ref fields) if fields.unnamed.len() == 1 => (),
+            Named(..) | Unnamed(..) => check_fields(&variant.fields, &variant.ident, &attrs),
+        }
+    }
+
+    find_external_subcommand(variants, parent_attribute);
+}
+
+/// A name of an argument which must be unique among the arguments of an app.
+#[derive(PartialEq)]
+enum ArgKey {
+    Id(String),
+    Short(char),
+    Long(String),
+}
+
+impl fmt::Display for ArgKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgKey::Id(id) => write!(f, "the argument id `{}`", id),
+            ArgKey::Short(short) => write!(f, "the short `-{}`", short),
+            ArgKey::Long(long) => write!(f, "the long `--{}`", long),
+        }
+    }
+}
+
+/// The id, short and long names of the argument of a field, along with the
+/// ones of its `--no-` companion, when they are known at expansion time.
+///
+/// The short name is the first char of the given string, like
+/// `Method::to_tokens` does at runtime.
+fn arg_keys(attrs: &Attrs) -> Vec<(ArgKey, Span)> {
+    let name = attrs.cased_name();
+    let mut keys = vec![(ArgKey::Id(name.value()), name.span())];
+
+    for method in attrs.methods() {
+        let lit = match syn::parse2::<syn::LitStr>(method.args().clone()) {
+            Ok(lit) => lit,
+            Err(_) => continue,
+        };
+        let span = method.name().span();
+        match &*method.name().to_string() {
+            "short" => {
+                if let Some(short) = lit.value().chars().next() {
+                    keys.push((ArgKey::Short(short), span));
+                }
+            }
+            "long" => keys.push((ArgKey::Long(lit.value()), span)),
+            _ => (),
+        }
+    }
+
+    if let Some(negated) = attrs.negated_name() {
+        let long = keys.iter().find_map(|(key, _)| match key {
+            ArgKey::Long(long) => Some(format!("no-{}", long)),
+            _ => None,
+        });
+        keys.push((ArgKey::Id(negated.value()), negated.span()));
+        keys.extend(long.map(|long| (ArgKey::Long(long), negated.span())));
+    }
+
+    keys
+}
+
+/// What the methods of some `Attrs` are called on.
+#[derive(Copy, Clone)]
+enum Target {
+    /// `clap::Arg`, for fields
+    Arg,
+    /// `clap::App`, for structs, enums and variants
+    App,
+}
+
+impl Target {
+    fn methods(self) -> &'static [(&'static str, usize)] {
+        match self {
+            Target::Arg => ARG_METHODS,
+            Target::App => APP_METHODS,
+        }
+    }
+
+    /// The attributes allowed here which aren't builder methods.
+    fn attrs(self) -> &'static [&'static str] {
+        match self {
+            Target::Arg => FIELD_ATTRS,
+            Target::App => STRUCT_ATTRS,
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Target::Arg => Target::App,
+            Target::App => Target::Arg,
+        }
+    }
+
+    fn arity(self, name: &str) -> Option<usize> {
+        self.methods()
+            .iter()
+            .find(|&&(method, _)| method == name)
+            .map(|&(_, arity)| arity)
+    }
+}
+
+/// Check that the methods of `attrs` are builder methods of `target`, called
+/// with the right number of arguments.
+///
+/// Unknown methods without any close match are left to the compiler, they
+/// may come from a newer clap.
+fn check_methods(attrs: &Attrs, target: Target) {
+    for method in attrs.methods() {
+        let name = method.name().to_string();
+        let span = method.name().span();
+
+        match (target.arity(&name), target.other().arity(&name)) {
+            (Some(arity), _) if arity != method.arity() => emit_error!(
+                span,
+                "`{}` takes {}, {} given",
+                name,
+                arguments(arity),
+                method.arity()
+            ),
+            (Some(_), _) => (),
+            (None, Some(_)) => match target {
+                Target::Arg => emit_error!(
+                    span,
+                    "`{}` is a method of `clap::App`, it is only allowed on structs, \
+                     enums and variants",
+                    name
+                ),
+                Target::App => emit_error!(
+                    span,
+                    "`{}` is a method of `clap::Arg`, it is only allowed on fields",
+                    name
+                ),
+            },
+            (None, None) => {
+                let candidates = target
+                    .methods()
+                    .iter()
+                    .map(|&(method, _)| method)
+                    .chain(target.attrs().iter().cloned());
+                if let Some(candidate) = closest(&name, candidates) {
+                    emit_error!(
+                        span, "unknown attribute `{}`", name;
+                        help = "did you mean `{}`?", candidate
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn arguments(n: usize) -> String {
+    match n {
+        1 => "1 argument".to_string(),
+        n => format!("{} arguments", n),
+    }
+}
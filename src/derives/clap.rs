@@ -12,18 +12,34 @@
 // commit#ea76fa1b1b273e65e3b0b1046643715b49bec51f which is licensed under the
 // MIT/Apache 2.0 license.
 use proc_macro2;
-use proc_macro_error::{abort, abort_call_site, set_dummy};
-use syn::{self, punctuated, spanned::Spanned, token};
+use proc_macro_error::{abort_call_site, emit_error, set_dummy};
+use syn::{
+    self, parse::Parser, punctuated, punctuated::Punctuated, spanned::Spanned, token, Token,
+};
 
-use super::{from_argmatches, into_app, sub_type, Attrs, Kind, Name, ParserKind, Ty};
+use super::{
+    bounded_generics, check_attrs, crate_path, from_argmatches, into_app, is_simple_ty,
+    named_fields, sub_type, with_crate_path, Attrs, Kind, Name, ParserKind, Ty,
+};
 
 /// Generate a block of code to add arguments/subcommands corresponding to
 /// the `fields` to an app.
+///
+/// When `prefix` is given, the ids, long names and environment variables of
+/// the arguments are prefixed by the value of this variable, the arguments
+/// are added to the `groups` variable and allowed to occur many times when
+/// the `repeated` variable is set.
+///
+/// When `env_prefix` is given, this variable holds the prefix of the
+/// environment variables of the arguments, see `gen_env_prefix_arg`.
 fn gen_app_augmentation(
     fields: &punctuated::Punctuated<syn::Field, token::Comma>,
     app_var: &syn::Ident,
     parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+    env_prefix: Option<&syn::Ident>,
 ) -> proc_macro2::TokenStream {
+    let env_prefix_arg = from_argmatches::gen_env_prefix_arg(parent_attribute, env_prefix);
     let mut subcmds = fields.iter().filter_map(|field| {
         let attrs = Attrs::from_field(&field, parent_attribute.casing());
         let kind = attrs.kind();
@@ -42,9 +58,30 @@ fn gen_app_augmentation(
                 }
             };
 
+            let augmentation = match env_prefix_arg {
+                // the enums which are not derived ignore the prefix
+                Some(ref env_prefix) => from_argmatches::gen_fallback_call(
+                    subcmd_type,
+                    "augment_subcommands_with_env_prefix",
+                    quote!(#app_var, #env_prefix),
+                    true,
+                    quote!(::clap::Subcommand),
+                    quote! {
+                        <'__clap_b>(
+                            app: ::clap::App<'__clap_b>,
+                            _env_prefix: Option<&str>
+                        ) -> ::clap::App<'__clap_b>
+                    },
+                    quote!(<Self as ::clap::Subcommand>::augment_subcommands(app)),
+                ),
+                None => quote! {
+                    <#subcmd_type as ::clap::Subcommand>::augment_subcommands( #app_var )
+                },
+            };
+
             let span = field.span();
             let ts = quote! {
-                let #app_var = <#subcmd_type>::augment_app( #app_var );
+                let #app_var = #augmentation;
                 #required
             };
             Some((span, ts))
@@ -52,30 +89,95 @@ fn gen_app_augmentation(
             None
         }
     });
+    // a second one is reported by `check_attrs`
     let subcmd = subcmds.next().map(|(_, ts)| ts);
-    if let Some((span, _)) = subcmds.next() {
-        abort!(
-            span,
-            "multiple subcommand sets are not allowed, that's the second"
-        );
-    }
 
     let args = fields.iter().filter_map(|field| {
         let attrs = Attrs::from_field(field, parent_attribute.casing());
         let kind = attrs.kind();
         match &*kind {
-            Kind::Subcommand(_) | Kind::Skip(_) => None,
-            Kind::FlattenStruct => {
-                let ty = &field.ty;
+            Kind::Subcommand(_) | Kind::ExternalSubcommand | Kind::Skip(_) => None,
+            Kind::FlattenStruct(ty) => {
+                let flattened_ty = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) | (Ty::Vec, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                let repeated = **ty == Ty::Vec;
+                let flatten_prefix = from_argmatches::gen_flatten_prefix(&attrs, prefix);
+                let group = match **ty {
+                    Ty::Option => {
+                        let name = attrs.cased_name();
+                        Some(match prefix {
+                            Some(prefix) => gen_prefixed_name(prefix, &quote!(#name)),
+                            None => quote!(#name),
+                        })
+                    }
+                    _ => None,
+                };
+                let groups = match (prefix, &group) {
+                    (Some(_), Some(_)) => quote! {
+                        &groups
+                            .iter()
+                            .cloned()
+                            .chain(::std::iter::once(group))
+                            .collect::<Vec<_>>()
+                    },
+                    (Some(_), None) => quote!(groups),
+                    (None, Some(_)) => quote!(&[group]),
+                    (None, None) => quote!(&[]),
+                };
+                let group = group.map(|group| {
+                    quote! {
+                        let group = #group;
+                        let #app_var = #app_var.group(
+                            ::clap::ArgGroup::with_name(group).multiple(true)
+                        );
+                    }
+                });
+                let flatten_prefix = match flatten_prefix {
+                    None if group.is_none() && !repeated => None,
+                    flatten_prefix => Some(flatten_prefix.unwrap_or_else(|| quote!(""))),
+                };
+                let (hidden_prefix, hidden_env_prefix) = from_argmatches::gen_flatten_prefixes(
+                    flatten_prefix.as_ref(),
+                    env_prefix_arg.as_ref(),
+                );
+                let augmentation = from_argmatches::gen_flatten_dispatch(
+                    flatten_prefix.as_ref(),
+                    env_prefix_arg.as_ref(),
+                    env_prefix,
+                    quote_spanned! { kind.span()=>
+                        <#flattened_ty as ::clap::Args>::augment_args(#app_var)
+                    },
+                    gen_flattened_augmentation(
+                        flattened_ty,
+                        app_var,
+                        quote! {
+                            #hidden_prefix,
+                            #groups,
+                            #repeated,
+                            #hidden_env_prefix
+                        },
+                        // see `gen_flatten_prefix`
+                        attrs.prefix().is_none() && **ty != Ty::Option,
+                    ),
+                );
+
+                let augmentation = match attrs.help_heading() {
+                    Some(heading) => gen_headed_augmentation(app_var, heading, augmentation),
+                    None => augmentation,
+                };
+
                 Some(quote_spanned! { kind.span()=>
-                    let #app_var = <#ty>::augment_app(#app_var);
-                    let #app_var = if <#ty>::is_subcommand() {
-                        #app_var.setting(::clap::AppSettings::SubcommandRequiredElseHelp)
-                    } else {
-                        #app_var
+                    let #app_var = {
+                        #group
+                        #augmentation
                     };
                 })
             }
+            // read from the environment on construction
+            Kind::Arg(_) if attrs.is_env_only() => None,
+
             Kind::Arg(ty) => {
                 let convert_type = match **ty {
                     Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
@@ -91,6 +193,9 @@ fn gen_app_augmentation(
                 let parser = attrs.parser();
                 let func = &parser.func;
                 let validator = match *parser.kind {
+                    // the rendered `default_value_t` may not parse back, the
+                    // value given by the user is checked on construction
+                    _ if attrs.default_value_t().is_some() => quote!(),
                     ParserKind::TryFromStr => quote_spanned! { func.span()=>
                         .validator(|s| {
                             #func(s.as_str())
@@ -104,9 +209,12 @@ fn gen_app_augmentation(
                     _ => quote!(),
                 };
 
+                let negated = attrs.negated_name();
                 let modifier = match **ty {
                     Ty::Bool => quote!(),
 
+                    Ty::Option if negated.is_some() => quote!(),
+
                     Ty::Option => quote_spanned! { ty.span()=>
                         .takes_value(true)
                         #validator
@@ -152,110 +260,692 @@ fn gen_app_augmentation(
                     }
                 };
 
+                let arg_enum = match **ty {
+                    Ty::Option | Ty::OptionOption | Ty::OptionVec | Ty::Vec => true,
+                    Ty::Other => !occurrences && !flag,
+                    Ty::Bool => false,
+                };
+                let arg_enum = if arg_enum
+                    && negated.is_none()
+                    && !attrs.has_custom_parser()
+                    && !attrs.has_method("possible_values")
+                {
+                    gen_arg_enum_values(convert_type)
+                } else {
+                    quote!()
+                };
+
                 let name = attrs.cased_name();
-                let methods = attrs.field_methods();
+                let (name, methods) = match prefix {
+                    Some(prefix) => (
+                        gen_prefixed_name(prefix, &quote!(#name)),
+                        gen_prefixed_methods(&attrs, prefix),
+                    ),
+                    None => (quote!(#name), attrs.field_methods()),
+                };
+
+                // the required arguments of optional and repeated flattened
+                // structs are checked when building them
+                let grouped = match prefix {
+                    Some(_) => quote! {
+                        let arg = groups.iter().fold(arg, |arg, group| arg.group(group));
+                        let arg = if repeated { arg.multiple_occurrences(true) } else { arg };
+                        let arg = if groups.is_empty() && !repeated {
+                            arg
+                        } else {
+                            arg.required(false)
+                        };
+                    },
+                    None => quote!(),
+                };
+
+                // an argument gets the heading of the app when added to it,
+                // its own one is given afterwards
+                let add_arg = |name: &proc_macro2::TokenStream| match attrs.help_heading() {
+                    Some(own) => quote! {
+                        #app_var
+                            .arg(arg)
+                            .mut_arg(#name, |arg| arg.help_heading(Some(#own)))
+                    },
+                    None => quote!( #app_var.arg(arg) ),
+                };
+
+                let negated = negated.map(|negated| match prefix {
+                    Some(prefix) => gen_prefixed_name(prefix, &quote!(#negated)),
+                    None => quote!(#negated),
+                });
+                // the companion is added last
+                let add_arg_last = add_arg(negated.as_ref().unwrap_or(&name));
+                let companion = negated.map(|negated| {
+                    let add_arg = add_arg(&name);
+                    let (long, help) = gen_negated_long(&attrs, prefix);
+                    quote! {
+                        let arg = arg.overrides_with(#negated);
+                        #grouped
+                        let #app_var = #add_arg;
+                        let arg = ::clap::Arg::with_name(#negated)
+                            .long(#long)
+                            .help(#help)
+                            .overrides_with(#name);
+                    }
+                });
+
+                // bound by its `env` method otherwise
+                let env_names = match attrs.find_method("env") {
+                    Some(_) => None,
+                    None => from_argmatches::gen_env_names(
+                        &attrs,
+                        ty,
+                        parent_attribute,
+                        prefix,
+                        env_prefix,
+                    ),
+                };
+                let env = env_names.map(|names| {
+                    // clap only knows of the first variable, the others are
+                    // read on construction when the argument is absent
+                    let fallbacks = attrs.env_names().into_iter().any(|names| names.len() > 1);
+                    let required = match **ty {
+                        Ty::Other if fallbacks => quote!( let arg = arg.required(false); ),
+                        _ => quote!(),
+                    };
+                    quote! {
+                        let arg = match (#names).and_then(|names| names.into_iter().next()) {
+                            Some(name) => arg.env(intern(name)),
+                            None => arg,
+                        };
+                        #required
+                    }
+                });
 
                 Some(quote_spanned! { field.span()=>
-                    let #app_var = #app_var.arg(
-                        ::clap::Arg::with_name(#name)
-                            #modifier
-                            #methods
-                    );
+                    let #app_var = {
+                        let arg = ::clap::Arg::with_name(#name)
+                            #modifier;
+                        #arg_enum
+                        let arg = arg #methods;
+                        #env
+                        #companion
+                        #grouped
+                        #add_arg_last
+                    };
                 })
             }
         }
     });
 
+    // the arguments of the flattened structs are only known at runtime, the
+    // others are checked by `check_attrs`
+    let assert_unique = fields.iter().find_map(|field| {
+        let attrs = Attrs::from_field(field, parent_attribute.casing());
+        match *attrs.kind() {
+            Kind::FlattenStruct(_) => Some(gen_assert_unique_args(app_var)),
+            _ => None,
+        }
+    });
+
+    let args = quote! {{
+        #( #args )*
+        #app_var
+    }};
+    let args = match parent_attribute.help_heading() {
+        Some(heading) => gen_headed_augmentation(app_var, heading, args),
+        None => args,
+    };
+
     let app_methods = parent_attribute.top_level_methods();
+    let display_order = gen_derive_display_order(parent_attribute.derive_display_order());
+    let intern_fn = gen_intern_fn();
     quote! {{
-        let #app_var = #app_var#app_methods;
-        #( #args )*
+        #intern_fn
+        let #app_var = #app_var#app_methods#display_order;
+        let #app_var = #args;
         #subcmd
+        #assert_unique
         #app_var
     }}
 }
 
-fn gen_augment_app_fn(
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+/// Generate the setting displaying the arguments and subcommands of an app
+/// in the order they are added, when `derive_display_order`.
+///
+/// The arguments of the flattened structs are added in place. The arguments
+/// of the subcommands keep their own order, unless their variant or enum is
+/// marked too. An explicit `display_order` still wins.
+fn gen_derive_display_order(derive_display_order: bool) -> proc_macro2::TokenStream {
+    if derive_display_order {
+        quote!( .setting(::clap::AppSettings::DeriveDisplayOrder) )
+    } else {
+        quote!()
+    }
+}
+
+/// Generate an expression adding arguments to `app_var` by `augmentation`,
+/// giving the `heading` to the added ones without a heading.
+///
+/// An argument is given the heading of the app when added to it, so the
+/// headings are given afterwards: the heading of a field comes first, then
+/// the one of its struct or variant, then the one where it is flattened.
+fn gen_headed_augmentation(
+    app_var: &syn::Ident,
+    heading: &syn::LitStr,
+    augmentation: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {{
+        let before: Vec<String> = #app_var
+            .get_arguments()
+            .map(|arg| arg.get_name().to_string())
+            .collect();
+        let #app_var = #augmentation;
+        let added: Vec<&'static str> = #app_var
+            .get_arguments()
+            .filter(|arg| {
+                arg.get_help_heading().is_none()
+                    && !before.iter().any(|name| name == arg.get_name())
+            })
+            .map(|arg| intern(arg.get_name().to_string()))
+            .collect();
+        added.into_iter().fold(#app_var, |#app_var, name| {
+            #app_var.mut_arg(name, |arg| arg.help_heading(Some(#heading)))
+        })
+    }}
+}
+
+/// Generate a debug assertion checking that the arguments of `app` have
+/// distinct ids, short and long names.
+fn gen_assert_unique_args(app_var: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        #[cfg(debug_assertions)]
+        {
+            let args: Vec<_> = #app_var.get_arguments().collect();
+            for (i, arg) in args.iter().enumerate() {
+                for other in &args[..i] {
+                    let (name, other_name) = (arg.get_name(), other.get_name());
+                    assert!(
+                        name != other_name,
+                        "the argument id `{}` is used twice",
+                        name
+                    );
+                    if let (Some(short), Some(other_short)) = (arg.get_short(), other.get_short()) {
+                        assert!(
+                            short != other_short,
+                            "the short `-{}` is used by both `{}` and `{}`",
+                            short, other_name, name
+                        );
+                    }
+                    if let (Some(long), Some(other_long)) = (arg.get_long(), other.get_long()) {
+                        assert!(
+                            long != other_long,
+                            "the long `--{}` is used by both `{}` and `{}`",
+                            long, other_name, name
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Generate the long name and the help of the `--no-<name>` companion of a
+/// negatable flag.
+fn gen_negated_long(
+    attrs: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    // checked in `Attrs::from_field`
+    let long = attrs.find_method("long").unwrap().args();
+    match (prefix, syn::parse2::<syn::LitStr>(long.clone())) {
+        (None, Ok(long)) => {
+            let negated = format!("no-{}", long.value());
+            let help = format!("Negates --{}", long.value());
+            (quote!(#negated), quote!(#help))
+        }
+        (prefix, _) => {
+            let long = match prefix {
+                Some(prefix) => quote!(format!("{}{}", #prefix, #long)),
+                None => quote!(#long),
+            };
+            (
+                quote!(intern(format!("no-{}", #long))),
+                quote!(intern(format!("Negates --{}", #long))),
+            )
+        }
+    }
+}
+
+/// Generate an expression adding the arguments of a flattened struct of type
+/// `ty` to `app_var`, passing it `args` after the app. Through the fallback
+/// of `gen_fallback_call`, the types which are not derived are flattened
+/// without prefix.
+fn gen_flattened_augmentation(
+    ty: &syn::Type,
+    app_var: &syn::Ident,
+    args: proc_macro2::TokenStream,
+    fallback: bool,
+) -> proc_macro2::TokenStream {
+    from_argmatches::gen_fallback_call(
+        ty,
+        "augment_args_with_prefix",
+        quote!(#app_var, #args),
+        fallback,
+        quote!(::clap::Args),
+        quote! {
+            <'__clap_b>(
+                app: ::clap::App<'__clap_b>,
+                _prefix: &str,
+                _groups: &[&'__clap_b str],
+                _repeated: bool,
+                _env_prefix: Option<&str>
+            ) -> ::clap::App<'__clap_b>
+        },
+        quote!(<Self as ::clap::Args>::augment_args(app)),
+    )
+}
+
+/// Generate the `intern` function giving a `&'static str` equal to the
+/// `String` it is passed.
+///
+/// The app borrows the names of its arguments, so the ones built at runtime
+/// are leaked. They are leaked once per thread, the next apps reusing them.
+pub fn gen_intern_fn() -> proc_macro2::TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        fn intern(name: ::std::string::String) -> &'static str {
+            ::std::thread_local! {
+                static NAMES: ::std::cell::RefCell<::std::collections::HashSet<&'static str>> =
+                    ::std::cell::RefCell::new(::std::collections::HashSet::new());
+            }
+            NAMES.with(|names| {
+                let mut names = names.borrow_mut();
+                match names.get(name.as_str()) {
+                    Some(name) => *name,
+                    None => {
+                        let name: &'static str = ::std::boxed::Box::leak(name.into_boxed_str());
+                        names.insert(name);
+                        name
+                    }
+                }
+            })
+        }
+    }
+}
+
+/// Generate an expression prefixing `name` by the value of `prefix`, see
+/// `gen_intern_fn`.
+fn gen_prefixed_name(
+    prefix: &syn::Ident,
+    name: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote!(intern(format!("{}{}", #prefix, #name)))
+}
+
+/// Generate the methods of an argument, prefixing its long name and its
+/// environment variable by the value of `prefix`.
+///
+/// The environment variable gets the prefix in uppercase with underscores in
+/// place of the other non-alphanumeric characters: `replica-` gives `REPLICA_`.
+///
+/// The methods referring to other arguments by id get the prefix too, the ids
+/// being the ones of the arguments of the same struct. The groups of the
+/// struct given to the app by its `group` methods are left as is.
+fn gen_prefixed_methods(attrs: &Attrs, prefix: &syn::Ident) -> proc_macro2::TokenStream {
+    let methods = attrs.methods().iter().map(|method| {
+        let name = method.name();
+        let args = method.args();
+        // the position of the id among the arguments of the method and, for
+        // a slice, its position in the tuples of the slice along with their
+        // length
+        let (index, slice) = match &*name.to_string() {
+            "long" => {
+                let long = gen_prefixed_name(prefix, args);
+                return quote!( .long(#long) );
+            }
+            "env" => {
+                let env_prefix = from_argmatches::gen_env_var_prefix(prefix);
+                let env = gen_prefixed_name(&syn::Ident::new("env_prefix", prefix.span()), args);
+                return quote!( .env({ let env_prefix = #env_prefix; #env }) );
+            }
+            "conflicts_with"
+            | "default_value_if"
+            | "default_value_if_os"
+            | "group"
+            | "overrides_with"
+            | "required_if"
+            | "required_if_eq"
+            | "required_unless"
+            | "required_unless_present"
+            | "requires" => (0, None),
+            "requires_if" => (1, None),
+            "conflicts_with_all"
+            | "groups"
+            | "overrides_with_all"
+            | "required_unless_all"
+            | "required_unless_one"
+            | "required_unless_present_all"
+            | "required_unless_present_any"
+            | "requires_all" => (0, Some(None)),
+            "required_if_eq_all" | "required_if_eq_any" | "required_ifs" => (0, Some(Some((0, 2)))),
+            "requires_ifs" => (0, Some(Some((1, 2)))),
+            "default_value_ifs" | "default_value_ifs_os" => (0, Some(Some((0, 3)))),
+            _ => return quote!( #method ),
+        };
+
+        let args = match Punctuated::<syn::Expr, Token![,]>::parse_terminated.parse2(args.clone()) {
+            Ok(args) => args,
+            // reported by the compiler
+            Err(_) => return quote!( #method ),
+        };
+        let args = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| match (i == index, slice) {
+                (false, _) => quote!(#arg),
+                (true, None) => gen_prefixed_name(prefix, &quote!(#arg)),
+                (true, Some(None)) => {
+                    let id = gen_prefixed_name(prefix, &quote!(id));
+                    quote!( &(#arg).iter().map(|id| #id).collect::<Vec<_>>() )
+                }
+                (true, Some(Some((index, len)))) => {
+                    let elements: Vec<_> = (0..len)
+                        .map(|i| {
+                            syn::Ident::new(&format!("e{}", i), proc_macro2::Span::call_site())
+                        })
+                        .collect();
+                    let prefixed = elements.iter().enumerate().map(|(i, element)| {
+                        if i == index {
+                            gen_prefixed_name(prefix, &quote!(#element))
+                        } else {
+                            quote!(#element)
+                        }
+                    });
+                    quote! {
+                        &(#arg)
+                            .iter()
+                            .map(|&( #( #elements ),* )| ( #( #prefixed ),* ))
+                            .collect::<Vec<_>>()
+                    }
+                }
+            });
+        quote!( .#name( #( #args ),* ) )
+    });
+
+    quote!( #( #methods )* )
+}
+
+/// Generate the statements restricting `arg` to the `VARIANTS` of `ty`
+/// when `ty` implements `ArgEnum`.
+///
+/// Whether a type implements a trait is unknown at expansion time, so this
+/// relies on autoref-based specialization: the method lookup picks the
+/// `ArgEnum` impl when it applies and falls back to a no-op otherwise.
+fn gen_arg_enum_values(ty: &syn::Type) -> proc_macro2::TokenStream {
+    quote_spanned! { ty.span()=>
+        let arg = {
+            struct ArgEnumProbe<T>(::std::marker::PhantomData<T>);
+
+            #[allow(dead_code)]
+            trait ViaArgEnum {
+                fn arg_enum(&self) -> Option<(&'static [&'static str], bool)>;
+            }
+
+            impl<T: ::clap::ArgEnum> ViaArgEnum for &ArgEnumProbe<T> {
+                fn arg_enum(&self) -> Option<(&'static [&'static str], bool)> {
+                    Some((T::VARIANTS, T::CASE_INSENSITIVE))
+                }
+            }
+
+            #[allow(dead_code)]
+            trait ViaOther {
+                fn arg_enum(&self) -> Option<(&'static [&'static str], bool)>;
+            }
+
+            impl<T> ViaOther for ArgEnumProbe<T> {
+                fn arg_enum(&self) -> Option<(&'static [&'static str], bool)> {
+                    None
+                }
+            }
+
+            match (&&ArgEnumProbe::<#ty>(::std::marker::PhantomData)).arg_enum() {
+                Some((variants, case_insensitive)) => arg
+                    .possible_values(variants)
+                    .case_insensitive(case_insensitive),
+                None => arg,
+            }
+        };
+    }
+}
+
+/// Generate the `Args::augment_args` method of a struct.
+pub fn gen_augment_args_fn(
+    name: &syn::Ident,
+    fields: &syn::Fields,
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let app_var = syn::Ident::new("app", proc_macro2::Span::call_site());
+    let augmentation = gen_app_augmentation(
+        &named_fields(fields, name),
+        &app_var,
+        parent_attribute,
+        None,
+        None,
+    );
+    quote! {
+        fn augment_args<'__clap_b>(
+            #app_var: ::clap::App<'__clap_b>
+        ) -> ::clap::App<'__clap_b> {
+            #augmentation
+        }
+    }
+}
+
+/// Generate the hidden `augment_args_with_prefix` function used to flatten
+/// a struct with a `prefix`, as an `Option` in which case its arguments are
+/// put in the `groups` of the optional structs containing it, as a
+/// `repeated` one whose arguments can occur many times, within a struct
+/// binding its arguments to environment variables with an `env_prefix`.
+pub fn gen_augment_args_with_prefix_fn(
+    name: &syn::Ident,
+    fields: &syn::Fields,
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     let app_var = syn::Ident::new("app", proc_macro2::Span::call_site());
-    let augmentation = gen_app_augmentation(fields, &app_var, parent_attribute);
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let env_prefix = syn::Ident::new("env_prefix", proc_macro2::Span::call_site());
+    let combination = from_argmatches::gen_env_prefix_combination(parent_attribute, &env_prefix);
+    let augmentation = gen_app_augmentation(
+        &named_fields(fields, name),
+        &app_var,
+        parent_attribute,
+        Some(&prefix),
+        Some(&env_prefix),
+    );
     quote! {
-        pub fn augment_app<'b>(
-            #app_var: ::clap::App<'b>
-        ) -> ::clap::App<'b> {
+        #[doc(hidden)]
+        #[allow(dead_code, unreachable_pub, unused_variables)]
+        pub fn augment_args_with_prefix<'__clap_b>(
+            #app_var: ::clap::App<'__clap_b>,
+            #prefix: &str,
+            groups: &[&'__clap_b str],
+            repeated: bool,
+            #env_prefix: Option<&str>
+        ) -> ::clap::App<'__clap_b> {
+            #combination
             #augmentation
         }
     }
 }
 
-fn gen_augment_app_for_enum(
+/// Find the variant marked with `external_subcommand`, if any, along with
+/// the `String` or `OsString` type of the arguments it collects.
+pub fn find_external_subcommand<'a>(
+    variants: &'a punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> Option<(&'a syn::Variant, &'a syn::Type)> {
+    let mut externals = variants.iter().filter_map(|variant| {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            parent_attribute.casing(),
+        );
+        match *attrs.kind() {
+            Kind::ExternalSubcommand => Some((variant, attrs.kind().span())),
+            _ => None,
+        }
+    });
+
+    let (variant, _) = externals.next()?;
+    if let Some((_, span)) = externals.next() {
+        emit_error!(
+            span,
+            "only one variant can be marked with `external_subcommand`, that's the second"
+        );
+    }
+
+    let str_ty = match variant.fields {
+        syn::Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            match (*Ty::from_syn_ty(ty), sub_type(ty)) {
+                (Ty::Vec, Some(str_ty))
+                    if is_simple_ty(str_ty, "String") || is_simple_ty(str_ty, "OsString") =>
+                {
+                    Some(str_ty)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    if str_ty.is_none() {
+        emit_error!(
+            variant.fields.span(),
+            "the `external_subcommand` variant must hold a single `Vec<String>` \
+             or `Vec<OsString>`"
+        );
+    }
+    str_ty.map(|str_ty| (variant, str_ty))
+}
+
+/// Generate an expression adding a subcommand for each of the `variants`
+/// to `app`, the `env_prefix` variable holding the prefix of the environment
+/// variables given by the parent.
+pub fn gen_augment_subcommands(
     variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     use syn::Fields::*;
 
-    let subcommands = variants.iter().map(|variant| {
+    let external = find_external_subcommand(variants, parent_attribute);
+    let allow_external = external.map(|_| {
+        quote! {
+            .setting(::clap::AppSettings::AllowExternalSubcommands)
+        }
+    });
+
+    let derive_display_order = parent_attribute.derive_display_order();
+
+    let subcommands = variants.iter().enumerate().filter_map(|(index, variant)| {
+        if let Some((external, _)) = external {
+            if external.ident == variant.ident {
+                return None;
+            }
+        }
+
         let attrs = Attrs::from_struct(
             variant.span(),
             &variant.attrs,
             Name::Derived(variant.ident.clone()),
             parent_attribute.casing(),
         );
+        // an explicit `display_order` comes last and wins
+        let order = if derive_display_order {
+            Some(quote!( .display_order(#index) ))
+        } else {
+            None
+        };
+        // the arguments of the variants follow the order of the enum
+        let args_order =
+            gen_derive_display_order(derive_display_order || attrs.derive_display_order());
         let app_var = syn::Ident::new("subcommand", proc_macro2::Span::call_site());
+        let env_prefix = syn::Ident::new("env_prefix", proc_macro2::Span::call_site());
         let arg_block = match variant.fields {
-            Named(ref fields) => gen_app_augmentation(&fields.named, &app_var, &attrs),
             Unit => quote!( #app_var ),
             Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
-                let ty = &unnamed[0];
-                quote_spanned! { ty.span() =>
-                    {
-                        let #app_var = <#ty>::augment_app(#app_var);
-                        if <#ty>::is_subcommand() {
-                            #app_var.setting(
-                                ::clap::AppSettings::SubcommandRequiredElseHelp
-                            )
-                        } else {
-                            #app_var
-                        }
+                let ty = &unnamed[0].ty;
+                let hidden = gen_flattened_augmentation(
+                    ty,
+                    &app_var,
+                    quote!("", &[], false, #env_prefix),
+                    true,
+                );
+                let augmentation = quote_spanned! { ty.span() =>
+                    match #env_prefix {
+                        Some(_) => #hidden,
+                        None => <#ty as ::clap::Args>::augment_args(#app_var),
+                    }
+                };
+                match attrs.help_heading() {
+                    Some(heading) => {
+                        let intern_fn = gen_intern_fn();
+                        let headed = gen_headed_augmentation(&app_var, heading, augmentation);
+                        quote!({ #intern_fn #headed })
                     }
+                    None => augmentation,
                 }
             }
-            Unnamed(..) => abort_call_site!("{}: tuple enums are not supported", variant.ident),
+            Named(..) | Unnamed(..) => gen_app_augmentation(
+                &named_fields(&variant.fields, &variant.ident),
+                &app_var,
+                &attrs,
+                None,
+                Some(&env_prefix),
+            ),
         };
 
         let name = attrs.cased_name();
         let from_attrs = attrs.top_level_methods();
+        let combination =
+            from_argmatches::gen_env_prefix_combination(parent_attribute, &env_prefix);
+        let variant_combination = from_argmatches::gen_env_prefix_combination(&attrs, &env_prefix);
 
-        quote! {
+        Some(quote! {
             .subcommand({
-                let #app_var = ::clap::App::new(#name);
+                #combination
+                #variant_combination
+                let #app_var = ::clap::App::new(#name)#args_order;
                 let #app_var = #arg_block;
-                #app_var#from_attrs
+                #app_var#order#from_attrs
             })
-        }
+        })
     });
 
     let app_methods = parent_attribute.top_level_methods();
 
     quote! {
-        pub fn augment_app<'b>(
-            app: ::clap::App<'b>
-        ) -> ::clap::App<'b> {
-            app #app_methods #( #subcommands )*
-        }
+        app #app_methods #allow_external #( #subcommands )*
     }
 }
 
-fn gen_from_subcommand(
+/// Generate the expression building the variant named after the
+/// `subcommand` variable, with the prefix of the environment variables held
+/// by the `env_prefix` variable.
+pub fn gen_from_subcommand(
     name: &syn::Ident,
     variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     use syn::Fields::*;
 
-    let match_arms = variants.iter().map(|variant| {
+    let external = find_external_subcommand(variants, parent_attribute);
+
+    let match_arms = variants.iter().filter_map(|variant| {
+        if let Some((external, _)) = external {
+            if external.ident == variant.ident {
+                return None;
+            }
+        }
+
         let attrs = Attrs::from_struct(
             variant.span(),
             &variant.attrs,
@@ -264,49 +954,226 @@ fn gen_from_subcommand(
         );
         let sub_name = attrs.cased_name();
         let variant_name = &variant.ident;
+        let env_prefix = syn::Ident::new("env_prefix", proc_macro2::Span::call_site());
         let constructor_block = match variant.fields {
-            Named(ref fields) => from_argmatches::gen_constructor(&fields.named, &attrs),
             Unit => quote!(),
             Unnamed(ref fields) if fields.unnamed.len() == 1 => {
-                let ty = &fields.unnamed[0];
-                quote!( ( <#ty as ::clap::FromArgMatches>::from_argmatches(matches) ) )
+                let ty = &fields.unnamed[0].ty;
+                let value = from_argmatches::gen_flattened_value(
+                    ty,
+                    &quote!(""),
+                    &quote!(#env_prefix),
+                    true,
+                );
+                quote_spanned! { ty.span()=>
+                    (match #env_prefix {
+                        Some(_) => #value,
+                        None => <#ty as ::clap::FromArgMatches>::try_from_argmatches(matches)?,
+                    })
+                }
             }
-            Unnamed(..) => abort_call_site!("{}: tuple enums are not supported", variant.ident),
+            Named(..) | Unnamed(..) => from_argmatches::gen_constructor(
+                &variant.fields,
+                &variant.ident,
+                &attrs,
+                None,
+                Some(&env_prefix),
+            ),
+        };
+        let combination =
+            from_argmatches::gen_env_prefix_combination(parent_attribute, &env_prefix);
+        let variant_combination = from_argmatches::gen_env_prefix_combination(&attrs, &env_prefix);
+
+        Some(quote! {
+            (#sub_name, Some(matches)) => {
+                #combination
+                #variant_combination
+                Ok(Some(#name :: #variant_name #constructor_block))
+            }
+        })
+    });
+
+    let external_arm = external.map(|(variant, str_ty)| {
+        let variant_name = &variant.ident;
+        let values_of = if is_simple_ty(str_ty, "OsString") {
+            quote!(values_of_os)
+        } else {
+            quote!(values_of)
         };
 
         quote! {
-            (#sub_name, Some(matches)) =>
-                Some(#name :: #variant_name #constructor_block)
+            (external, Some(matches)) => Ok(Some(#name :: #variant_name(
+                ::std::iter::once(<#str_ty>::from(external))
+                    .chain(
+                        matches.#values_of("")
+                            .into_iter()
+                            .flatten()
+                            .map(<#str_ty>::from)
+                    )
+                    .collect::<Vec<_>>()
+            ))),
         }
     });
 
     quote! {
-        pub fn from_subcommand<'b>(
-            sub: (&'b str, Option<&'b ::clap::ArgMatches>)
-        ) -> Option<Self> {
-            match sub {
-                #( #match_arms ),*,
-                _ => None
+        match subcommand {
+            #( #match_arms )*
+            #external_arm
+            _ => Ok(None)
+        }
+    }
+}
+
+/// Generate the statements updating `self` from the `subcommand` variable,
+/// with the prefix of the environment variables held by the `env_prefix`
+/// variable.
+///
+/// When the parsed subcommand is the current variant its fields are updated
+/// in place, otherwise the whole value is replaced by the variant built by
+/// the `from_subcommand` expression.
+pub fn gen_update_from_subcommand(
+    name: &syn::Ident,
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+    from_subcommand: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    let external = find_external_subcommand(variants, parent_attribute);
+
+    let match_arms = variants.iter().filter_map(|variant| {
+        if let Some((external, _)) = external {
+            if external.ident == variant.ident {
+                return None;
+            }
+        }
+
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            parent_attribute.casing(),
+        );
+        let sub_name = attrs.cased_name();
+        let variant_name = &variant.ident;
+        let env_prefix = syn::Ident::new("env_prefix", proc_macro2::Span::call_site());
+        let (pattern, updater) = match variant.fields {
+            Unit => (quote!(), quote!()),
+            Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                let update = from_argmatches::gen_flattened_update(
+                    ty,
+                    &quote!(*inner),
+                    &quote!(""),
+                    &quote!(#env_prefix),
+                    true,
+                );
+                let updater = quote_spanned! { ty.span()=>
+                    match #env_prefix {
+                        Some(_) => { #update }
+                        None => ::clap::FromArgMatches::try_update_from_argmatches(inner, matches)?,
+                    }
+                };
+                (quote!((ref mut inner)), updater)
+            }
+            Named(..) | Unnamed(..) => {
+                let bindings = from_argmatches::gen_bindings(&variant.fields);
+                let pattern = match variant.fields {
+                    Named(..) => quote!({ #( ref mut #bindings ),* }),
+                    _ => quote!(( #( ref mut #bindings ),* )),
+                };
+                let updater = from_argmatches::gen_updater(
+                    &variant.fields,
+                    &variant.ident,
+                    &attrs,
+                    false,
+                    None,
+                    Some(&env_prefix),
+                );
+                (pattern, updater)
+            }
+        };
+        let combination =
+            from_argmatches::gen_env_prefix_combination(parent_attribute, &env_prefix);
+        let variant_combination = from_argmatches::gen_env_prefix_combination(&attrs, &env_prefix);
+
+        Some(quote! {
+            #name :: #variant_name #pattern if name == #sub_name => {
+                #combination
+                #variant_combination
+                #updater
+            }
+        })
+    });
+
+    quote! {
+        if let (name, Some(matches)) = subcommand {
+            match *self {
+                #( #match_arms )*
+                _ => {
+                    if let Some(parsed) = #from_subcommand? {
+                        *self = parsed;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generate the `Subcommand::has_subcommand` method, telling whether one of
+/// the `variants` is named `name`.
+pub fn gen_has_subcommand(
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let body = if find_external_subcommand(variants, parent_attribute).is_some() {
+        quote!(true)
+    } else {
+        let names = variants.iter().map(|variant| {
+            Attrs::from_struct(
+                variant.span(),
+                &variant.attrs,
+                Name::Derived(variant.ident.clone()),
+                parent_attribute.casing(),
+            )
+            .cased_name()
+        });
+        quote! {
+            match name {
+                #( #names )|* => true,
+                _ => false,
             }
         }
+    };
+
+    quote! {
+        fn has_subcommand(name: &str) -> bool {
+            #body
+        }
     }
 }
 
 fn clap_impl_for_struct(
     name: &syn::Ident,
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    generics: &syn::Generics,
+    fields: &syn::Fields,
     attrs: &[syn::Attribute],
 ) -> proc_macro2::TokenStream {
-    let into_app_impl = into_app::gen_into_app_impl_for_struct(name, attrs);
+    let into_app_impl = into_app::gen_into_app_impl_for_struct(name, generics, fields, attrs);
     let into_app_impl_tokens = into_app_impl.tokens;
-    let augment_app_fn = gen_augment_app_fn(fields, &into_app_impl.attrs);
-    let from_argmatches_impl =
-        from_argmatches::gen_from_argmatches_impl_for_struct(name, fields, &into_app_impl.attrs);
-    let parse_fns = gen_parse_fns(name);
+    let from_argmatches_impl = from_argmatches::gen_from_argmatches_impl_for_struct(
+        name,
+        generics,
+        fields,
+        &into_app_impl.attrs,
+    );
+    let parse_fns = gen_parse_fns();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
         #[allow(unused_variables)]
-        impl ::clap::Clap for #name { }
+        impl #impl_generics ::clap::Clap for #name #ty_generics #where_clause { }
 
         #into_app_impl_tokens
 
@@ -314,46 +1181,41 @@ fn clap_impl_for_struct(
 
         #[allow(dead_code, unreachable_code)]
         #[doc(hidden)]
-        impl #name {
-            #augment_app_fn
-
+        impl #impl_generics #name #ty_generics #where_clause {
             #parse_fns
-
-            pub fn is_subcommand() -> bool { false }
         }
     }
 }
 
 fn clap_impl_for_enum(
     name: &syn::Ident,
+    generics: &syn::Generics,
     variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
     attrs: &[syn::Attribute],
 ) -> proc_macro2::TokenStream {
-    let into_app_impl = into_app::gen_into_app_impl_for_enum(name, attrs);
+    let into_app_impl = into_app::gen_into_app_impl_for_enum(name, generics, variants, attrs);
     let into_app_impl_tokens = into_app_impl.tokens;
-    let augment_app_fn = gen_augment_app_for_enum(variants, &into_app_impl.attrs);
-    let from_argmatches_impl = from_argmatches::gen_from_argmatches_impl_for_enum(name);
-    let from_subcommand = gen_from_subcommand(name, variants, &into_app_impl.attrs);
-    let parse_fns = gen_parse_fns(name);
+    let from_argmatches_impl = from_argmatches::gen_from_argmatches_impl_for_enum(
+        name,
+        generics,
+        variants,
+        &into_app_impl.attrs,
+    );
+    let parse_fns = gen_parse_fns();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
         #[allow(unused_variables)]
-        impl ::clap::Clap for #name { }
+        impl #impl_generics ::clap::Clap for #name #ty_generics #where_clause { }
 
         #into_app_impl_tokens
 
         #from_argmatches_impl
 
-        #[allow(unused_variables, dead_code, unreachable_code)]
+        #[allow(dead_code, unreachable_code)]
         #[doc(hidden)]
-        impl #name {
-            #augment_app_fn
-
-            #from_subcommand
-
+        impl #impl_generics #name #ty_generics #where_clause {
             #parse_fns
-
-            pub fn is_subcommand() -> bool { true }
         }
     }
 }
@@ -362,66 +1224,93 @@ pub fn derive_clap(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     use syn::Data::*;
 
     let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let crate_path = crate_path(&input.attrs);
 
-    set_dummy(quote! {
-        impl ::clap::Clap for #struct_name {}
+    let dummy = quote! {
+        impl #impl_generics ::clap::Clap for #struct_name #ty_generics #where_clause {}
 
-        impl ::clap::IntoApp for #struct_name {
-            fn into_app<'b>() -> ::clap::App<'b> {
+        impl #impl_generics ::clap::IntoApp for #struct_name #ty_generics #where_clause {
+            fn into_app<'__clap_b>() -> ::clap::App<'__clap_b> {
                 unimplemented!()
             }
         }
 
-        impl ::clap::FromArgMatches for #struct_name {
+        impl #impl_generics ::clap::FromArgMatches for #struct_name #ty_generics #where_clause {
             fn from_argmatches(m: &::clap::ArgMatches) -> Self {
                 unimplemented!()
             }
+
+            fn try_from_argmatches(
+                m: &::clap::ArgMatches
+            ) -> ::std::result::Result<Self, ::clap::Error> {
+                unimplemented!()
+            }
         }
 
-        impl #struct_name {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
             fn parse() -> Self {
                 unimplemented!();
             }
         }
-    });
+    };
+    set_dummy(with_crate_path(dummy, crate_path.as_ref()));
 
-    match input.data {
-        Struct(syn::DataStruct {
-            fields: syn::Fields::Named(ref fields),
-            ..
-        }) => clap_impl_for_struct(struct_name, &fields.named, &input.attrs),
-        Enum(ref e) => clap_impl_for_enum(struct_name, &e.variants, &input.attrs),
-        _ => abort_call_site!("clap_derive only supports non-tuple structs and enums"),
-    }
+    check_attrs(input);
+    let generics = bounded_generics(input);
+    let tokens = match input.data {
+        Struct(syn::DataStruct { ref fields, .. }) => {
+            clap_impl_for_struct(struct_name, &generics, fields, &input.attrs)
+        }
+        Enum(ref e) => clap_impl_for_enum(struct_name, &generics, &e.variants, &input.attrs),
+        _ => abort_call_site!("clap_derive only supports structs and enums"),
+    };
+    with_crate_path(tokens, crate_path.as_ref())
 }
 
-fn gen_parse_fns(name: &syn::Ident) -> proc_macro2::TokenStream {
+fn gen_parse_fns() -> proc_macro2::TokenStream {
     quote! {
         #[allow(unreachable_pub)]
-        pub fn parse() -> #name {
+        pub fn parse() -> Self {
             use ::clap::{FromArgMatches, IntoApp};
-            #name::from_argmatches(&#name::into_app().get_matches())
+            Self::from_argmatches(&Self::into_app().get_matches())
         }
         #[allow(unreachable_pub)]
-        pub fn try_parse() -> ::std::result::Result<#name, ::clap::Error> {
+        pub fn try_parse() -> ::std::result::Result<Self, ::clap::Error> {
+            use ::clap::{FromArgMatches, IntoApp};
+            Self::try_from_argmatches(&Self::into_app().try_get_matches()?)
+        }
+        #[allow(unreachable_pub)]
+        pub fn parse_from<__I>(itr: __I) -> Self
+        where
+            __I: ::std::iter::IntoIterator,
+            __I::Item: Into<::std::ffi::OsString> + Clone {
+            use ::clap::{FromArgMatches, IntoApp};
+            Self::from_argmatches(&Self::into_app().get_matches_from(itr))
+        }
+        #[allow(unreachable_pub)]
+        pub fn try_parse_from<__I>(itr: __I) -> ::std::result::Result<Self, ::clap::Error>
+        where
+            __I: ::std::iter::IntoIterator,
+            __I::Item: Into<::std::ffi::OsString> + Clone {
             use ::clap::{FromArgMatches, IntoApp};
-            Ok(#name::from_argmatches(&#name::into_app().try_get_matches()?))
+            Self::try_from_argmatches(&Self::into_app().try_get_matches_from(itr)?)
         }
         #[allow(unreachable_pub)]
-        pub fn parse_from<I, T>(itr: I) -> #name
+        pub fn update_from<__I>(&mut self, itr: __I)
         where
-            I: ::std::iter::IntoIterator<Item = T>,
-            T: Into<::std::ffi::OsString> + Clone {
+            __I: ::std::iter::IntoIterator,
+            __I::Item: Into<::std::ffi::OsString> + Clone {
             use ::clap::{FromArgMatches, IntoApp};
-            #name::from_argmatches(&#name::into_app().get_matches_from(itr))
+            self.update_from_argmatches(&Self::into_app().get_matches_from(itr))
         }
         #[allow(unreachable_pub)]
-        pub fn try_parse_from<I, T>(itr: I) -> ::std::result::Result<#name, ::clap::Error>
+        pub fn try_update_from<__I>(&mut self, itr: __I) -> ::std::result::Result<(), ::clap::Error>
         where
-            I: ::std::iter::IntoIterator<Item = T>,
-            T: Into<::std::ffi::OsString> + Clone {
+            __I: ::std::iter::IntoIterator,
+            __I::Item: Into<::std::ffi::OsString> + Clone {
             use ::clap::{FromArgMatches, IntoApp};
-            Ok(#name::from_argmatches(&#name::into_app().try_get_matches_from(itr)?))
+            self.try_update_from_argmatches(&Self::into_app().try_get_matches_from(itr)?)
         }
     }
 }
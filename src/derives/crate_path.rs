@@ -0,0 +1,131 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Custom path to the `clap` crate
+//!
+//! The generated code always refers to clap as `::clap`. When the derived
+//! type is annotated with `#[clap(crate = "some::path")]`, every such path
+//! of the output is rewritten to start with `some::path` instead.
+
+use proc_macro2::{Delimiter, Group, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn;
+
+/// Get the path given by `#[clap(crate = "...")]` in `attrs`, if any.
+///
+/// This only looks for the `crate` attribute and never aborts, so it can be
+/// used before `set_dummy`. A malformed path is reported later on, when the
+/// attributes are parsed for real.
+pub fn crate_path(attrs: &[syn::Attribute]) -> Option<TokenStream> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("clap"))
+        .filter_map(|attr| match attr.tokens.clone().into_iter().next() {
+            Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Parenthesis => {
+                find_crate_path(group.stream())
+            }
+            _ => None,
+        })
+        .next_back()
+}
+
+fn find_crate_path(tokens: TokenStream) -> Option<TokenStream> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    tokens
+        .split(|token| match token {
+            TokenTree::Punct(punct) => punct.as_char() == ',',
+            _ => false,
+        })
+        .filter_map(|attr| match attr {
+            [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Literal(lit)]
+                if name == "crate" && eq.as_char() == '=' =>
+            {
+                let lit: syn::LitStr = syn::parse2(lit.clone().into_token_stream()).ok()?;
+                let path: syn::Path = lit.parse().ok()?;
+                Some(path.into_token_stream())
+            }
+            _ => None,
+        })
+        .next_back()
+}
+
+/// Replace the leading `::clap` of every path in `tokens` with `path`.
+pub fn with_crate_path(tokens: TokenStream, path: Option<&TokenStream>) -> TokenStream {
+    match path {
+        Some(path) => replace_clap_root(tokens, path),
+        None => tokens,
+    }
+}
+
+fn replace_clap_root(tokens: TokenStream, path: &TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut res = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_clap_root(&tokens, i) {
+            res.extend(respan(path.clone(), tokens[i + 2].span()));
+            i += 3;
+            continue;
+        }
+
+        res.push(match tokens[i] {
+            TokenTree::Group(ref group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), replace_clap_root(group.stream(), path));
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            ref token => token.clone(),
+        });
+        i += 1;
+    }
+    res.into_iter().collect()
+}
+
+/// Whether `tokens[i..]` starts with `::clap` and the `::` isn't the
+/// separator of a longer path, like in `foo::clap::`.
+fn is_clap_root(tokens: &[TokenTree], i: usize) -> bool {
+    let is_punct = |i: usize, spacing: Spacing| match tokens.get(i) {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == ':' && punct.spacing() == spacing,
+        _ => false,
+    };
+    let is_clap = match tokens.get(i + 2) {
+        Some(TokenTree::Ident(ident)) => ident == "clap",
+        _ => false,
+    };
+    if !(is_punct(i, Spacing::Joint) && is_punct(i + 1, Spacing::Alone) && is_clap) {
+        return false;
+    }
+
+    match i.checked_sub(1).map(|prev| &tokens[prev]) {
+        // `::clap` right after an identifier is only a path root when that
+        // identifier is a keyword, like in `<T as ::clap::Args>`
+        Some(TokenTree::Ident(ident)) => KEYWORDS.iter().any(|kw| ident == kw),
+        Some(TokenTree::Punct(punct)) => {
+            !(punct.as_char() == ':' && punct.spacing() == Spacing::Joint)
+        }
+        _ => true,
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "dyn", "else", "for", "if", "impl", "in", "let", "match", "move",
+    "mut", "pub", "ref", "return", "static", "type", "unsafe", "use", "where", "while",
+];
+
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
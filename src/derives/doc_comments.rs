@@ -0,0 +1,543 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of doc comments into help messages
+//!
+//! Doc comments are markdown, written for rustdoc. They are turned into plain
+//! text for the terminal: paragraphs are kept apart, lists keep their
+//! markers, code blocks are kept verbatim and the inline markup is removed,
+//! links only keeping their text. The sections of the doc comment of an app,
+//! like `# Examples`, are rendered like the sections of the help.
+
+/// A block of a doc comment.
+enum Block {
+    Paragraph(String),
+    Heading(String),
+    /// The rendered items, with their markers and indentation.
+    List(Vec<String>),
+    Code(Vec<String>),
+}
+
+/// The help messages of a doc comment.
+pub struct DocHelp {
+    /// The first paragraph, if the doc comment doesn't start with a section.
+    pub short: Option<String>,
+    /// The whole text, when there is more than the first paragraph.
+    pub long: Option<String>,
+    /// The sections following the text, like `# Examples`, when they are
+    /// split out.
+    pub sections: Option<String>,
+}
+
+/// The help messages rendered from the doc comment `lines`, without the
+/// comment markers.
+///
+/// With `sections`, the text starting at the first heading is rendered as
+/// help-style sections, like the `USAGE:` of clap, instead of being part of
+/// the long help. `None` is returned for an empty doc comment.
+pub fn render_doc_comment(lines: &[String], sections: bool) -> Option<DocHelp> {
+    let mut blocks = parse_blocks(&unindent(lines));
+    if blocks.is_empty() {
+        return None;
+    }
+
+    let heading = blocks.iter().position(|block| match block {
+        Block::Heading(_) => sections,
+        _ => false,
+    });
+    let sections = heading.map(|heading| render_sections(&blocks.split_off(heading)));
+
+    let short = blocks.first().map(|block| match block {
+        // command-line help typically omits the trailing period of the
+        // complete sentences of rustdoc
+        Block::Paragraph(text) if blocks.len() > 1 => text.trim_end_matches('.').to_string(),
+        block => render_block(block),
+    });
+
+    let long = if blocks.len() > 1 {
+        let blocks: Vec<String> = blocks.iter().map(render_block).collect();
+        Some(blocks.join("\n\n"))
+    } else {
+        None
+    };
+
+    Some(DocHelp {
+        short,
+        long,
+        sections,
+    })
+}
+
+/// The help messages of the doc comment `lines`, keeping their line breaks
+/// and indentation, for `verbatim_doc_comment`.
+///
+/// Like `render_doc_comment`, the first paragraph is the short help and the
+/// whole text is the long help when there is more than that.
+pub fn verbatim_doc_comment(lines: &[String]) -> Option<DocHelp> {
+    // the space rustdoc puts after `///`
+    let lines: Vec<&str> = lines
+        .iter()
+        .flat_map(|line| line.split('\n'))
+        .map(|line| line.trim_end_matches('\r'))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect();
+    let start = lines.iter().position(|line| !line.trim().is_empty())?;
+    let end = lines.iter().rposition(|line| !line.trim().is_empty())? + 1;
+    let lines = &lines[start..end];
+
+    let (short, long) = match lines.iter().position(|line| line.trim().is_empty()) {
+        Some(blank) => (lines[..blank].join("\n"), Some(lines.join("\n"))),
+        None => (lines.join("\n"), None),
+    };
+    Some(DocHelp {
+        short: Some(short),
+        long,
+        sections: None,
+    })
+}
+
+/// Render `blocks`, starting with a heading, like the sections of the help:
+/// the headings in uppercase followed by a colon, and their content
+/// indented.
+///
+/// ```text
+/// EXAMPLES:
+///     $ git clone https://github.com/clap-rs/clap
+/// ```
+fn render_sections(blocks: &[Block]) -> String {
+    let mut res = String::new();
+    let mut after_heading = false;
+    for block in blocks {
+        match block {
+            Block::Heading(heading) => {
+                if !res.is_empty() {
+                    res.push_str("\n\n");
+                }
+                res.push_str(&heading.trim_end_matches(':').to_uppercase());
+                res.push(':');
+                after_heading = true;
+            }
+            block => {
+                if !after_heading {
+                    res.push('\n');
+                }
+                for line in render_block(block).split('\n') {
+                    res.push('\n');
+                    if !line.is_empty() {
+                        res.push_str("    ");
+                        res.push_str(line);
+                    }
+                }
+                after_heading = false;
+            }
+        }
+    }
+    res
+}
+
+/// Split the doc `lines` on newlines and remove their common indentation,
+/// like rustdoc does.
+fn unindent(lines: &[String]) -> Vec<String> {
+    let lines: Vec<&str> = lines.iter().flat_map(|line| line.split('\n')).collect();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| strip_indent(line, indent).trim_end_matches('\r'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The number of whitespace chars `line` starts with.
+fn indentation(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// `line` without its first `count` whitespace chars, or all of them when it
+/// starts with fewer.
+fn strip_indent(line: &str, count: usize) -> &str {
+    let start = line
+        .char_indices()
+        .take_while(|&(_, c)| c.is_whitespace())
+        .take(count)
+        .last()
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    &line[start..]
+}
+
+fn parse_blocks(lines: &[String]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = &lines[i];
+        let trimmed = line.trim();
+        i += 1;
+
+        if trimmed.is_empty() || is_link_definition(trimmed) {
+            continue;
+        }
+
+        if let Some((fence, info)) = code_fence(trimmed) {
+            let indent = indentation(line);
+            let hidden_lines = is_rust_code(info);
+            let mut code = Vec::new();
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                let line = &lines[i];
+                i += 1;
+                if hidden_lines && (line.trim() == "#" || line.trim_start().starts_with("# ")) {
+                    continue;
+                }
+                code.push(strip_indent(line, indent).to_string());
+            }
+            // the closing fence
+            i += 1;
+            blocks.push(Block::Code(code));
+        } else if let Some(heading) = heading(trimmed) {
+            blocks.push(Block::Heading(render_inline(heading)));
+        } else if list_marker(trimmed).is_some() {
+            let mut items: Vec<(String, String)> = Vec::new();
+            i -= 1;
+            while i < lines.len() {
+                let line = &lines[i];
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    // a blank line only ends the list when no item follows
+                    match lines[i..].iter().find(|line| !line.trim().is_empty()) {
+                        Some(next) if list_marker(next.trim()).is_some() => {
+                            i += 1;
+                            continue;
+                        }
+                        _ => break,
+                    }
+                }
+                if code_fence(trimmed).is_some() || heading(trimmed).is_some() {
+                    break;
+                }
+
+                match list_marker(trimmed) {
+                    Some((marker, text)) => {
+                        let indent = &line[..line.len() - line.trim_start().len()];
+                        items.push((format!("{}{} ", indent, marker), text.to_string()));
+                    }
+                    // the continuation of the previous item
+                    None => {
+                        let text = &mut items.last_mut().unwrap().1;
+                        text.push(' ');
+                        text.push_str(trimmed);
+                    }
+                }
+                i += 1;
+            }
+
+            let items = items
+                .into_iter()
+                .map(|(marker, text)| format!("{}{}", marker, render_inline(&text)))
+                .collect();
+            blocks.push(Block::List(items));
+        } else {
+            let mut text = trimmed.to_string();
+            while i < lines.len() {
+                let next = lines[i].trim();
+                if next.is_empty()
+                    || code_fence(next).is_some()
+                    || heading(next).is_some()
+                    || list_marker(next).is_some()
+                {
+                    break;
+                }
+                i += 1;
+
+                // two trailing spaces or a backslash are a hard line break
+                let previous = &lines[i - 2];
+                if previous.ends_with("  ") || previous.ends_with('\\') {
+                    if text.ends_with('\\') {
+                        text.pop();
+                    }
+                    text.push('\n');
+                } else {
+                    text.push(' ');
+                }
+                text.push_str(next);
+            }
+            blocks.push(Block::Paragraph(render_inline(&text)));
+        }
+    }
+
+    blocks
+}
+
+fn render_block(block: &Block) -> String {
+    match block {
+        Block::Paragraph(text) | Block::Heading(text) => text.clone(),
+        Block::List(lines) | Block::Code(lines) => lines.join("\n"),
+    }
+}
+
+/// The fence and the info string of the line opening a code block.
+fn code_fence(line: &str) -> Option<(&str, &str)> {
+    ["```", "~~~"]
+        .iter()
+        .find(|fence| line.starts_with(*fence))
+        .map(|fence| {
+            (
+                *fence,
+                line.trim_start_matches(fence.chars().next().unwrap())
+                    .trim(),
+            )
+        })
+}
+
+/// Whether a code block is rust code, in which rustdoc hides the lines
+/// starting with `#`.
+fn is_rust_code(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| {
+            attr == "rust"
+                || attr == "ignore"
+                || attr == "no_run"
+                || attr == "should_panic"
+                || attr == "compile_fail"
+                || attr.starts_with("edition")
+        })
+}
+
+/// The text of a heading line, like `# Examples`.
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    if (1..=6).contains(&level) && (text.is_empty() || text.starts_with(' ')) {
+        Some(text.trim().trim_end_matches('#').trim_end())
+    } else {
+        None
+    }
+}
+
+/// The marker and the text of a list item line, like `- item` or `1. item`.
+fn list_marker(line: &str) -> Option<(String, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let (marker, rest) = match line[digits..].chars().next() {
+        Some('-') | Some('*') | Some('+') if digits == 0 => ("-".to_string(), &line[1..]),
+        Some(c @ '.') | Some(c @ ')') if digits > 0 && digits < 10 => {
+            (line[..=digits].to_string(), &line[digits + c.len_utf8()..])
+        }
+        _ => return None,
+    };
+
+    if rest.starts_with(' ') {
+        Some((marker, rest.trim_start()))
+    } else {
+        None
+    }
+}
+
+/// Whether `line` defines the target of a reference link, like
+/// `[clap]: https://docs.rs/clap`.
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && !line.starts_with("[]:") && line.contains("]:")
+}
+
+/// Remove the inline markup of `text`: code spans, emphasis, links and
+/// escapes.
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut res = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        match c {
+            '\\' if rest.len() > 1 && rest[1].is_ascii_punctuation() => {
+                res.push(rest[1]);
+                i += 2;
+            }
+
+            '`' => {
+                let run = run_length(rest, '`');
+                match find_run(&chars, i + run, '`', run) {
+                    Some(end) => {
+                        let code: String = chars[i + run..end].iter().collect();
+                        res.push_str(code.trim());
+                        i = end + run;
+                    }
+                    None => {
+                        res.extend(&rest[..run]);
+                        i += run;
+                    }
+                }
+            }
+
+            '*' | '_' => {
+                let run = std::cmp::min(run_length(rest, c), 3);
+                let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+                let end = if can_open(prev, rest.get(run).cloned(), c) {
+                    find_closing(&chars, i + run, c, run)
+                } else {
+                    None
+                };
+                match end {
+                    Some(end) => {
+                        let inner: String = chars[i + run..end].iter().collect();
+                        res.push_str(&render_inline(&inner));
+                        i = end + run;
+                    }
+                    None => {
+                        res.extend(&rest[..run]);
+                        i += run;
+                    }
+                }
+            }
+
+            '[' if i == 0 || !chars[i - 1].is_alphanumeric() => match parse_link(&chars, i) {
+                Some((label, url, end)) => {
+                    let label = render_inline(&label);
+                    match url {
+                        Some(ref url)
+                            if (url.starts_with("http://") || url.starts_with("https://"))
+                                && *url != label =>
+                        {
+                            res.push_str(&format!("{} <{}>", label, url))
+                        }
+                        _ => res.push_str(&label),
+                    }
+                    i = end;
+                }
+                None => {
+                    res.push(c);
+                    i += 1;
+                }
+            },
+
+            // autolinks, like `<https://docs.rs>`
+            '<' => match rest.iter().position(|&c| c == '>') {
+                Some(end)
+                    if rest[1..end].iter().collect::<String>().contains("://")
+                        && !rest[1..end].iter().any(|c| c.is_whitespace()) =>
+                {
+                    res.extend(&rest[1..end]);
+                    i += end + 1;
+                }
+                _ => {
+                    res.push(c);
+                    i += 1;
+                }
+            },
+
+            _ => {
+                res.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    res
+}
+
+fn run_length(chars: &[char], c: char) -> usize {
+    chars.iter().take_while(|&&other| other == c).count()
+}
+
+/// The position of the next run of exactly `len` chars `c` from `start`.
+fn find_run(chars: &[char], start: usize, c: char, len: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        let run = run_length(&chars[i..], c);
+        if run == len {
+            return Some(i);
+        }
+        i += std::cmp::max(run, 1);
+    }
+    None
+}
+
+/// Whether a run of `c` between `prev` and `next` opens an emphasis. An
+/// underscore within a word, like in `snake_case`, doesn't.
+fn can_open(prev: Option<char>, next: Option<char>, c: char) -> bool {
+    let next_ok = next.map(char::is_whitespace) == Some(false);
+    let prev_ok = c == '*' || prev.map(char::is_alphanumeric) != Some(true);
+    next_ok && prev_ok
+}
+
+/// The position of the run of `len` chars `c` closing an emphasis opened
+/// before `start`.
+fn find_closing(chars: &[char], start: usize, c: char, len: usize) -> Option<usize> {
+    let mut i = start;
+    while let Some(end) = find_run(chars, i, c, len) {
+        let prev_ok = end > start && !chars[end - 1].is_whitespace();
+        let next_ok =
+            c == '*' || chars.get(end + len).map(|next| next.is_alphanumeric()) != Some(true);
+        if prev_ok && next_ok {
+            return Some(end);
+        }
+        i = end + len;
+    }
+    None
+}
+
+/// Parse the link starting at `chars[start]`, returning its label, its url
+/// when inline, and the position after it.
+///
+/// Intra-doc links, like ``[`Type`]``, and reference links only give their
+/// label.
+fn parse_link(chars: &[char], start: usize) -> Option<(String, Option<String>, usize)> {
+    let close = matching(chars, start, '[', ']')?;
+    let label: String = chars[start + 1..close].iter().collect();
+    if label.trim().is_empty() {
+        return None;
+    }
+
+    match chars.get(close + 1) {
+        Some('(') => {
+            let end = matching(chars, close + 1, '(', ')')?;
+            let url: String = chars[close + 2..end].iter().collect();
+            // drop the title, like in `[clap](https://docs.rs/clap "docs")`
+            let url = url.split_whitespace().next().unwrap_or("").to_string();
+            Some((label, Some(url), end + 1))
+        }
+        Some('[') => {
+            let end = matching(chars, close + 1, '[', ']')?;
+            Some((label, None, end + 1))
+        }
+        // a shortcut reference, or an intra-doc link to a path, unlike
+        // the brackets of `[default: 3]`
+        _ if label.starts_with('`') || !label.contains(char::is_whitespace) => {
+            Some((label, None, close + 1))
+        }
+        _ => None,
+    }
+}
+
+/// The position of the `close` char matching the `open` one at `start`.
+fn matching(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
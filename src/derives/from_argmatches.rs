@@ -13,56 +13,114 @@
 // MIT/Apache 2.0 license.
 use std::env;
 
+use heck::CamelCase;
 use proc_macro2;
+use proc_macro_error::abort_call_site;
 use syn;
-use syn::punctuated;
 use syn::spanned::Spanned as _;
-use syn::token;
+use syn::{punctuated, token};
 
-use super::{spanned::Sp, sub_type, Attrs, Kind, Name, ParserKind, Ty, DEFAULT_CASING};
+use super::{
+    bounded_generics, check_attrs,
+    clap::{gen_from_subcommand, gen_intern_fn, gen_update_from_subcommand},
+    crate_path, named_fields,
+    spanned::Sp,
+    sub_type, with_crate_path, Attrs, Kind, Name, ParserKind, Ty, DEFAULT_CASING,
+};
 
 pub fn derive_from_argmatches(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     use syn::Data::*;
 
     let struct_name = &input.ident;
+    check_attrs(input);
+    let generics = bounded_generics(input);
+
+    let name = env::var("CARGO_PKG_NAME")
+        .ok()
+        .unwrap_or_else(String::default);
+    let attrs = Attrs::from_struct(
+        proc_macro2::Span::call_site(),
+        &input.attrs,
+        Name::Assigned(syn::LitStr::new(&name, proc_macro2::Span::call_site())),
+        Sp::call_site(DEFAULT_CASING),
+    );
+
     let inner_impl = match input.data {
-        Struct(syn::DataStruct {
-            fields: syn::Fields::Named(ref fields),
-            ..
-        }) => {
-            let name = env::var("CARGO_PKG_NAME")
-                .ok()
-                .unwrap_or_else(String::default);
-
-            let attrs = Attrs::from_struct(
-                proc_macro2::Span::call_site(),
-                &input.attrs,
-                Name::Assigned(syn::LitStr::new(&name, proc_macro2::Span::call_site())),
-                Sp::call_site(DEFAULT_CASING),
-            );
-
-            gen_from_argmatches_impl_for_struct(struct_name, &fields.named, &attrs)
-        }
-        // Enum(ref e) => clap_for_enum_impl(struct_name, &e.variants, &input.attrs),
-        _ => panic!("clap_derive only supports non-tuple structs"), // and enums"),
+        Struct(syn::DataStruct { ref fields, .. }) => {
+            gen_from_argmatches_impl_for_struct(struct_name, &generics, fields, &attrs)
+        }
+        Enum(ref e) => {
+            gen_from_argmatches_impl_for_enum(struct_name, &generics, &e.variants, &attrs)
+        }
+        _ => abort_call_site!("clap_derive only supports structs and enums"),
     };
 
-    quote!(#inner_impl)
+    with_crate_path(inner_impl, crate_path(&input.attrs).as_ref())
 }
 
 pub fn gen_from_argmatches_impl_for_struct(
     name: &syn::Ident,
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    generics: &syn::Generics,
+    fields: &syn::Fields,
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     let from_argmatches_fn = gen_from_argmatches_fn_for_struct(name, fields, parent_attribute);
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let env_prefix = syn::Ident::new("env_prefix", proc_macro2::Span::call_site());
+    let combination = gen_env_prefix_combination(parent_attribute, &env_prefix);
+    let field_block = gen_constructor(
+        fields,
+        name,
+        parent_attribute,
+        Some(&prefix),
+        Some(&env_prefix),
+    );
+    let updater = gen_updater(
+        fields,
+        name,
+        parent_attribute,
+        true,
+        Some(&prefix),
+        Some(&env_prefix),
+    );
+    let repeated_fn =
+        gen_from_argmatches_repeated_fn(name, fields, parent_attribute, &prefix, &env_prefix);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
-        impl ::clap::FromArgMatches for #name {
+        impl #impl_generics ::clap::FromArgMatches for #name #ty_generics #where_clause {
             #from_argmatches_fn
         }
 
-        impl From<::clap::ArgMatches> for #name {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc(hidden)]
+            #[allow(dead_code, unreachable_pub)]
+            pub fn try_from_argmatches_with_prefix(
+                matches: &::clap::ArgMatches,
+                #prefix: &str,
+                #env_prefix: Option<&str>
+            ) -> ::std::result::Result<Self, ::clap::Error> {
+                #combination
+                Ok(#name #field_block)
+            }
+
+            #[doc(hidden)]
+            #[allow(dead_code, unreachable_pub)]
+            pub fn try_update_from_argmatches_with_prefix(
+                &mut self,
+                matches: &::clap::ArgMatches,
+                #prefix: &str,
+                #env_prefix: Option<&str>
+            ) -> ::std::result::Result<(), ::clap::Error> {
+                #combination
+                #updater
+                Ok(())
+            }
+
+            #repeated_fn
+        }
+
+        impl #impl_generics From<::clap::ArgMatches> for #name #ty_generics #where_clause {
             fn from(m: ::clap::ArgMatches) -> Self {
                 use ::clap::FromArgMatches;
                 <Self as ::clap::FromArgMatches>::from_argmatches(&m)
@@ -75,25 +133,301 @@ pub fn gen_from_argmatches_impl_for_struct(
 
 pub fn gen_from_argmatches_fn_for_struct(
     struct_name: &syn::Ident,
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    fields: &syn::Fields,
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
-    let field_block = gen_constructor(fields, parent_attribute);
+    let field_block = gen_constructor(fields, struct_name, parent_attribute, None, None);
+    let updater = gen_updater(fields, struct_name, parent_attribute, true, None, None);
 
     quote! {
         fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
-            #struct_name #field_block
+            <Self as ::clap::FromArgMatches>::try_from_argmatches(matches)
+                .unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        fn try_from_argmatches(
+            matches: &::clap::ArgMatches
+        ) -> ::std::result::Result<Self, ::clap::Error> {
+            Ok(#struct_name #field_block)
+        }
+
+        fn update_from_argmatches(&mut self, matches: &::clap::ArgMatches) {
+            <Self as ::clap::FromArgMatches>::try_update_from_argmatches(self, matches)
+                .unwrap_or_else(|e| panic!("{}", e))
+        }
+
+        fn try_update_from_argmatches(
+            &mut self,
+            matches: &::clap::ArgMatches
+        ) -> ::std::result::Result<(), ::clap::Error> {
+            #updater
+            Ok(())
+        }
+    }
+}
+
+/// Whether the struct can be flattened as a `Vec`: only the structs with
+/// arguments, and whose fields are all arguments, can be.
+fn is_repeatable(name: &syn::Ident, fields: &syn::Fields, parent_attribute: &Attrs) -> bool {
+    let mut has_args = false;
+    for field in &named_fields(fields, name) {
+        let attrs = Attrs::from_field(field, parent_attribute.casing());
+        match *attrs.kind() {
+            Kind::Arg(_) if !attrs.is_env_only() => has_args = true,
+            Kind::Arg(_) | Kind::Skip(_) => (),
+            _ => return false,
+        }
+    }
+    has_args
+}
+
+/// Generate the hidden `try_from_argmatches_repeated` function used to flatten
+/// a struct as a `Vec`.
+///
+/// The structs which can't be flattened as a `Vec` don't get it, so that
+/// flattening them as `repeated` fails to compile, with "no function or
+/// associated item named `try_from_argmatches_repeated`".
+///
+/// Every occurrence of the first argument of the struct, its leader, starts
+/// a new instance which gets the values given after it and before the next
+/// one. This relies on the indices of the values, through a view over
+/// `matches` mimicking the methods of `ArgMatches`.
+fn gen_from_argmatches_repeated_fn(
+    name: &syn::Ident,
+    fields: &syn::Fields,
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+    env_prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    if !is_repeatable(name, fields, parent_attribute) {
+        return quote!();
+    }
+
+    let mut args = vec![];
+    for field in &named_fields(fields, name) {
+        let attrs = Attrs::from_field(field, parent_attribute.casing());
+        match *attrs.kind() {
+            Kind::Arg(ref ty) if !attrs.is_env_only() => args.push((attrs.clone(), ty.clone())),
+            _ => (),
+        }
+    }
+    let leader = gen_arg_id(&args[0].0.cased_name(), Some(prefix));
+
+    let checks = args.iter().skip(1).map(|(attrs, ty)| {
+        let id = gen_arg_id(&attrs.cased_name(), Some(prefix));
+        let single = match (**ty, &*attrs.parser().kind) {
+            (Ty::Bool, _) | (Ty::Vec, _) | (Ty::OptionVec, _) => quote!(),
+            (_, ParserKind::FromOccurrences) | (_, ParserKind::FromFlag) => quote!(),
+            _ => quote! {
+                for (start, end) in &ranges {
+                    if indices.iter().filter(|index| *start < **index && **index < *end).count() > 1 {
+                        return Err(::clap::Error::with_description(
+                            &format!(
+                                "The argument '{}' was provided more than once for the same '{}'",
+                                #id, #leader
+                            ),
+                            ::clap::ErrorKind::UnexpectedMultipleUsage
+                        ));
+                    }
+                }
+            },
+        };
+        quote! {
+            let indices: Vec<usize> = matches
+                .indices_of(#id)
+                .filter(|_| matches.occurrences_of(#id) > 0)
+                .into_iter()
+                .flatten()
+                .collect();
+            if indices.iter().any(|index| *index < first) {
+                return Err(::clap::Error::with_description(
+                    &format!(
+                        "The argument '{}' cannot be used before the first '{}'",
+                        #id, #leader
+                    ),
+                    ::clap::ErrorKind::ArgumentConflict
+                ));
+            }
+            #single
+        }
+    });
+
+    // an argument given for some instances takes no default value in clap,
+    // so the others get it from here
+    let defaults = args.iter().filter_map(|(attrs, _)| {
+        let id = attrs.cased_name();
+        attrs.find_method("default_value").map(|method| {
+            let value = method.args();
+            quote!( (format!("{}{}", #prefix, #id), #value) )
+        })
+    });
+
+    let intern_fn = gen_intern_fn();
+    let combination = gen_env_prefix_combination(parent_attribute, env_prefix);
+    let field_block = gen_constructor(
+        fields,
+        name,
+        parent_attribute,
+        Some(prefix),
+        Some(env_prefix),
+    );
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(dead_code, unreachable_pub)]
+        pub fn try_from_argmatches_repeated(
+            matches: &::clap::ArgMatches,
+            #prefix: &str,
+            #env_prefix: Option<&str>
+        ) -> ::std::result::Result<Vec<Self>, ::clap::Error> {
+            #intern_fn
+            #combination
+            // the part of `matches` between two occurrences of the leader
+            struct Record<'a> {
+                matches: &'a ::clap::ArgMatches,
+                defaults: &'a [(String, &'a str)],
+                start: usize,
+                end: usize,
+            }
+
+            impl<'a> Record<'a> {
+                fn select<T>(
+                    &self,
+                    id: &str,
+                    values: Option<impl Iterator<Item = T>>
+                ) -> Option<::std::vec::IntoIter<T>> {
+                    if self.matches.occurrences_of(id) == 0 {
+                        // default values and environment variables
+                        return values.map(|values| values.collect::<Vec<_>>().into_iter());
+                    }
+                    let indices = self.matches.indices_of(id)?;
+                    let values: Vec<T> = values?
+                        .zip(indices)
+                        .filter(|(_, index)| self.start <= *index && *index < self.end)
+                        .map(|(value, _)| value)
+                        .collect();
+                    if values.is_empty() {
+                        None
+                    } else {
+                        Some(values.into_iter())
+                    }
+                }
+
+                fn occurrences_of(&self, id: &str) -> u64 {
+                    self.matches
+                        .indices_of(id)
+                        .filter(|_| self.matches.occurrences_of(id) > 0)
+                        .into_iter()
+                        .flatten()
+                        .filter(|index| self.start <= *index && *index < self.end)
+                        .count() as u64
+                }
+
+                fn default_of(&self, id: &str) -> Option<&'a str> {
+                    self.defaults
+                        .iter()
+                        .find(|(default_id, _)| default_id == id)
+                        .map(|(_, value)| *value)
+                }
+
+                fn is_present(&self, id: &str) -> bool {
+                    self.occurrences_of(id) > 0
+                        || (self.matches.is_present(id) && self.matches.occurrences_of(id) == 0)
+                        || self.default_of(id).is_some()
+                }
+
+                fn values_of(&self, id: &str) -> Option<::std::vec::IntoIter<&'a str>> {
+                    self.select(id, self.matches.values_of(id))
+                        .or_else(|| self.default_of(id).map(|value| vec![value].into_iter()))
+                }
+
+                fn value_of(&self, id: &str) -> Option<&'a str> {
+                    self.values_of(id)?.next()
+                }
+
+                fn values_of_os(
+                    &self,
+                    id: &str
+                ) -> Option<::std::vec::IntoIter<&'a ::std::ffi::OsStr>> {
+                    self.select(id, self.matches.values_of_os(id)).or_else(|| {
+                        self.default_of(id)
+                            .map(|value| vec![::std::ffi::OsStr::new(value)].into_iter())
+                    })
+                }
+
+                fn value_of_os(&self, id: &str) -> Option<&'a ::std::ffi::OsStr> {
+                    self.values_of_os(id)?.next()
+                }
+            }
+
+            let defaults: Vec<(String, &str)> = vec![ #( #defaults ),* ];
+            let mut leaders: Vec<usize> = matches
+                .indices_of(#leader)
+                .filter(|_| matches.occurrences_of(#leader) > 0)
+                .into_iter()
+                .flatten()
+                .collect();
+            leaders.sort();
+            let first = leaders.first().cloned().unwrap_or(::std::usize::MAX);
+            let ranges: Vec<(usize, usize)> = leaders
+                .iter()
+                .enumerate()
+                .map(|(i, start)| (*start, leaders.get(i + 1).cloned().unwrap_or(::std::usize::MAX)))
+                .collect();
+
+            #( { #checks } )*
+
+            ranges
+                .into_iter()
+                .map(|(start, end)| -> ::std::result::Result<Self, ::clap::Error> {
+                    let matches = &Record {
+                        matches,
+                        defaults: &defaults,
+                        start,
+                        end,
+                    };
+                    Ok(#name #field_block)
+                })
+                .collect()
         }
     }
 }
 
+/// Generate an expression building the error returned when a required
+/// subcommand is missing.
+pub fn gen_missing_subcommand_error() -> proc_macro2::TokenStream {
+    quote! {
+        ::clap::Error::with_description(
+            "A subcommand is required but one was not provided",
+            ::clap::ErrorKind::MissingSubcommand
+        )
+    }
+}
+
+/// Generate the `{ field: value, ... }` block building a struct or a variant
+/// from `matches`.
+///
+/// Tuple structs and variants are built with the `{ 0: value, ... }` syntax,
+/// their fields being named after `parent` for argument purposes.
+///
+/// When `prefix` is given, the argument ids are prefixed by the value of
+/// this variable. When `env_prefix` is given, this variable holds the prefix
+/// of the environment variables, see `gen_env_prefix_arg`.
 pub fn gen_constructor(
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    fields: &syn::Fields,
+    parent: &syn::Ident,
     parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+    env_prefix: Option<&syn::Ident>,
 ) -> proc_macro2::TokenStream {
-    let fields = fields.iter().map(|field| {
+    let env_prefix_arg = gen_env_prefix_arg(parent_attribute, env_prefix);
+    let named = named_fields(fields, parent);
+    let fields = named.iter().enumerate().map(|(index, field)| {
         let attrs = Attrs::from_field(field, parent_attribute.casing());
-        let field_name = field.ident.as_ref().unwrap();
+        let field_name = match fields {
+            syn::Fields::Unnamed(_) => syn::Member::Unnamed(index.into()),
+            _ => syn::Member::Named(field.ident.clone().unwrap()),
+        };
         let kind = attrs.kind();
         match &*attrs.kind() {
             Kind::Subcommand(ty) => {
@@ -103,16 +437,85 @@ pub fn gen_constructor(
                 };
                 let unwrapper = match **ty {
                     Ty::Option => quote!(),
-                    _ => quote_spanned!( ty.span()=> .unwrap() ),
+                    _ => {
+                        let missing = gen_missing_subcommand_error();
+                        quote_spanned!( ty.span()=> .ok_or_else(|| #missing)? )
+                    }
+                };
+                let value = match env_prefix_arg {
+                    Some(ref env_prefix) => gen_subcommand_value(subcmd_type, env_prefix),
+                    None => quote_spanned! { kind.span()=>
+                        <#subcmd_type as ::clap::Subcommand>::try_from_subcommand(matches.subcommand())?
+                    },
                 };
                 quote_spanned! { kind.span()=>
-                    #field_name: <#subcmd_type>::from_subcommand(matches.subcommand())#unwrapper
+                    #field_name: #value #unwrapper
                 }
             }
 
-            Kind::FlattenStruct => quote_spanned! { kind.span()=>
-                #field_name: ::clap::FromArgMatches::from_argmatches(matches)
-            },
+            Kind::ExternalSubcommand => unreachable!(),
+
+            Kind::FlattenStruct(ty) => {
+                let flattened_ty = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) | (Ty::Vec, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                let flatten_prefix = gen_flatten_prefix(&attrs, prefix);
+                let flatten_env_prefix = env_prefix_arg.clone().unwrap_or_else(|| quote!(None));
+                // see `gen_flatten_prefix`
+                let fallback = attrs.prefix().is_none();
+                match **ty {
+                    Ty::Option => {
+                        let group = gen_arg_id(&attrs.cased_name(), prefix);
+                        let flatten_prefix = flatten_prefix.unwrap_or_else(|| quote!(""));
+                        let value = gen_flattened_value(
+                            flattened_ty,
+                            &flatten_prefix,
+                            &flatten_env_prefix,
+                            false,
+                        );
+                        // The group is also filled by environment variables and
+                        // default values, so only the arguments given on the
+                        // command line make the struct present.
+                        quote_spanned! { kind.span()=>
+                            #field_name: if matches.occurrences_of(#group) > 0 {
+                                Some(#value)
+                            } else {
+                                None
+                            }
+                        }
+                    }
+                    Ty::Vec => {
+                        let flatten_prefix = flatten_prefix.unwrap_or_else(|| quote!(""));
+                        quote_spanned! { kind.span()=>
+                            #field_name: <#flattened_ty>::try_from_argmatches_repeated(
+                                matches,
+                                #flatten_prefix,
+                                #flatten_env_prefix
+                            )?
+                        }
+                    }
+                    _ => {
+                        let (hidden_prefix, hidden_env_prefix) =
+                            gen_flatten_prefixes(flatten_prefix.as_ref(), env_prefix_arg.as_ref());
+                        let value = gen_flatten_dispatch(
+                            flatten_prefix.as_ref(),
+                            env_prefix_arg.as_ref(),
+                            env_prefix,
+                            quote_spanned! { kind.span()=>
+                                ::clap::FromArgMatches::try_from_argmatches(matches)?
+                            },
+                            gen_flattened_value(
+                                flattened_ty,
+                                &hidden_prefix,
+                                &hidden_env_prefix,
+                                fallback,
+                            ),
+                        );
+                        quote_spanned!(kind.span()=> #field_name: #value)
+                    }
+                }
+            }
 
             Kind::Skip(val) => match val {
                 None => quote_spanned!(kind.span()=> #field_name: Default::default()),
@@ -120,112 +523,824 @@ pub fn gen_constructor(
             },
 
             Kind::Arg(ty) => {
-                use self::ParserKind::*;
-
-                let parser = attrs.parser();
-                let func = &parser.func;
-                let span = parser.kind.span();
-                let (value_of, values_of, parse) = match *parser.kind {
-                    FromStr => (
-                        quote_spanned!(span=> value_of),
-                        quote_spanned!(span=> values_of),
-                        func.clone(),
-                    ),
-                    TryFromStr => (
-                        quote_spanned!(span=> value_of),
-                        quote_spanned!(span=> values_of),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
-                    ),
-                    FromOsStr => (
-                        quote_spanned!(span=> value_of_os),
-                        quote_spanned!(span=> values_of_os),
-                        func.clone(),
-                    ),
-                    TryFromOsStr => (
-                        quote_spanned!(span=> value_of_os),
-                        quote_spanned!(span=> values_of_os),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
-                    ),
-                    FromOccurrences => (
-                        quote_spanned!(span=> occurrences_of),
-                        quote!(),
-                        func.clone(),
-                    ),
-                    FromFlag => (quote!(), quote!(), func.clone()),
-                };
+                let field_value = gen_arg_value(&attrs, ty, parent_attribute, prefix, env_prefix);
+                quote_spanned!(field.span()=> #field_name: #field_value )
+            }
+        }
+    });
 
-                let flag = *attrs.parser().kind == ParserKind::FromFlag;
-                let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
-                let name = attrs.cased_name();
-                let field_value = match **ty {
-                    Ty::Bool => quote_spanned! { ty.span()=>
-                        matches.is_present(#name)
-                    },
+    quote! {{
+        #( #fields ),*
+    }}
+}
 
-                    Ty::Option => quote_spanned! { ty.span()=>
-                        matches.#value_of(#name)
-                            .map(#parse)
+/// Generate the statements overwriting the fields of a struct or a variant
+/// whose arguments are present in `matches`.
+///
+/// Fields are accessed through `self` for structs and through the bindings
+/// made by `gen_bindings` for variants. The fields bound to environment
+/// variables are also overwritten when one of them is set, their value taking
+/// precedence over the default one.
+pub fn gen_updater(
+    fields: &syn::Fields,
+    parent: &syn::Ident,
+    parent_attribute: &Attrs,
+    use_self: bool,
+    prefix: Option<&syn::Ident>,
+    env_prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let env_prefix_arg = gen_env_prefix_arg(parent_attribute, env_prefix);
+    let named = named_fields(fields, parent);
+    let bindings = gen_bindings(fields);
+    let updates = named.iter().zip(bindings).enumerate().map(|(index, (field, binding))| {
+        let attrs = Attrs::from_field(field, parent_attribute.casing());
+        let place = if use_self {
+            let member = match fields {
+                syn::Fields::Unnamed(_) => syn::Member::Unnamed(index.into()),
+                _ => syn::Member::Named(field.ident.clone().unwrap()),
+            };
+            quote!(self.#member)
+        } else {
+            quote!((*#binding))
+        };
+
+        let kind = attrs.kind();
+        match &*kind {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                let update = |subcommand: proc_macro2::TokenStream| match env_prefix_arg {
+                    Some(ref env_prefix) => {
+                        gen_subcommand_update(subcmd_type, &subcommand, env_prefix)
+                    }
+                    None => quote_spanned! { kind.span()=>
+                        <#subcmd_type as ::clap::Subcommand>::try_update_from_subcommand(
+                            #subcommand,
+                            matches.subcommand()
+                        )?;
                     },
+                };
+                match **ty {
+                    Ty::Option => {
+                        let update = update(quote!(subcommand));
+                        let value = match env_prefix_arg {
+                            Some(ref env_prefix) => gen_subcommand_value(subcmd_type, env_prefix),
+                            None => quote_spanned! { kind.span()=>
+                                <#subcmd_type as ::clap::Subcommand>::try_from_subcommand(
+                                    matches.subcommand()
+                                )?
+                            },
+                        };
+                        quote_spanned! { kind.span()=>
+                            match #place {
+                                Some(ref mut subcommand) => { #update }
+                                None => #place = #value,
+                            }
+                        }
+                    }
+                    _ => update(quote!(&mut #place)),
+                }
+            }
 
-                    Ty::OptionOption => quote_spanned! { ty.span()=>
-                        if matches.is_present(#name) {
-                            Some(matches.#value_of(#name).map(#parse))
-                        } else {
-                            None
+            Kind::FlattenStruct(ty) => {
+                let flattened_ty = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) | (Ty::Vec, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                let flatten_prefix = gen_flatten_prefix(&attrs, prefix);
+                let flatten_env_prefix = env_prefix_arg.clone().unwrap_or_else(|| quote!(None));
+                let fallback = attrs.prefix().is_none();
+                match **ty {
+                    Ty::Option => {
+                        let group = gen_arg_id(&attrs.cased_name(), prefix);
+                        let flatten_prefix = flatten_prefix.unwrap_or_else(|| quote!(""));
+                        let value = gen_flattened_value(
+                            flattened_ty,
+                            &flatten_prefix,
+                            &flatten_env_prefix,
+                            false,
+                        );
+                        let update = gen_flattened_update(
+                            flattened_ty,
+                            &quote!(*flattened),
+                            &flatten_prefix,
+                            &flatten_env_prefix,
+                            false,
+                        );
+                        // only the command line counts, see `gen_constructor`
+                        quote_spanned! { kind.span()=>
+                            if matches.occurrences_of(#group) > 0 {
+                                match #place {
+                                    Some(ref mut flattened) => { #update }
+                                    None => #place = Some(#value),
+                                }
+                            }
                         }
-                    },
+                    }
+                    Ty::Vec => {
+                        let flatten_prefix = flatten_prefix.unwrap_or_else(|| quote!(""));
+                        quote_spanned! { kind.span()=>
+                            {
+                                let records = <#flattened_ty>::try_from_argmatches_repeated(
+                                    matches,
+                                    #flatten_prefix,
+                                    #flatten_env_prefix
+                                )?;
+                                if !records.is_empty() {
+                                    #place = records;
+                                }
+                            }
+                        }
+                    }
+                    _ => {
+                        let (hidden_prefix, hidden_env_prefix) =
+                            gen_flatten_prefixes(flatten_prefix.as_ref(), env_prefix_arg.as_ref());
+                        let update = gen_flatten_dispatch(
+                            flatten_prefix.as_ref(),
+                            env_prefix_arg.as_ref(),
+                            env_prefix,
+                            quote_spanned! { kind.span()=>
+                                ::clap::FromArgMatches::try_update_from_argmatches(&mut #place, matches)?
+                            },
+                            gen_flattened_update(
+                                flattened_ty,
+                                &place,
+                                &hidden_prefix,
+                                &hidden_env_prefix,
+                                fallback,
+                            ),
+                        );
+                        quote!( #update; )
+                    }
+                }
+            }
 
-                    Ty::OptionVec => quote_spanned! { ty.span()=>
-                        if matches.is_present(#name) {
-                            Some(matches.#values_of(#name)
-                                 .map(|v| v.map(#parse).collect())
-                                 .unwrap_or_else(Vec::new))
-                        } else {
-                            None
+            Kind::ExternalSubcommand | Kind::Skip(_) => quote!(),
+
+            Kind::Arg(ty) => {
+                let id = gen_arg_id(&attrs.cased_name(), prefix);
+                let field_value = gen_arg_value(&attrs, ty, parent_attribute, prefix, env_prefix);
+                // an `env_only` field has no argument in `matches`
+                let present = if attrs.is_env_only() {
+                    None
+                } else {
+                    Some(quote!(matches.occurrences_of(#id) > 0))
+                };
+                let negated = attrs.negated_name().map(|negated| {
+                    let negated = gen_arg_id(&negated, prefix);
+                    quote!(matches.occurrences_of(#negated) > 0)
+                });
+                let env = gen_env_names(&attrs, ty, parent_attribute, prefix, env_prefix).map(
+                    |names| {
+                        quote! {
+                            (#names)
+                                .into_iter()
+                                .flatten()
+                                .any(|name| ::std::env::var_os(name).is_some())
                         }
                     },
+                );
+                let conditions = present.into_iter().chain(negated).chain(env);
+                quote_spanned! { field.span()=>
+                    if #( #conditions )||* {
+                        #place = #field_value;
+                    }
+                }
+            }
+        }
+    });
 
-                    Ty::Vec => quote_spanned! { ty.span()=>
-                        matches.#values_of(#name)
-                            .map(|v| v.map(#parse).collect())
-                            .unwrap_or_else(Vec::new)
-                    },
+    quote!( #( #updates )* )
+}
 
-                    Ty::Other if occurrences => quote_spanned! { ty.span()=>
-                        #parse(matches.#value_of(#name))
-                    },
+/// Generate the names binding the fields of a variant in a pattern.
+pub fn gen_bindings(fields: &syn::Fields) -> Vec<syn::Ident> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match field.ident {
+            Some(ref ident) => ident.clone(),
+            None => syn::Ident::new(&format!("__field{}", index), field.span()),
+        })
+        .collect()
+}
 
-                    Ty::Other if flag => quote_spanned! { ty.span()=>
-                        #parse(matches.is_present(#name))
-                    },
+/// Generate an expression giving the id of the argument `name`, prefixed by
+/// the value of the `prefix` variable if any.
+pub fn gen_arg_id(name: &syn::LitStr, prefix: Option<&syn::Ident>) -> proc_macro2::TokenStream {
+    match prefix {
+        Some(prefix) => quote!(&*format!("{}{}", #prefix, #name)),
+        None => quote!(#name),
+    }
+}
 
-                    Ty::Other => quote_spanned! { ty.span()=>
-                        matches.#value_of(#name)
-                            .map(#parse)
-                            .unwrap()
-                    },
-                };
+/// Generate an expression giving the prefix of the arguments of a flattened
+/// field, combining its own `prefix` with the one of its parent.
+///
+/// Returns `None` when the field is not prefixed at all, in which case the
+/// `Args` and `FromArgMatches` traits are used.
+///
+/// Only derived structs can be flattened with a prefix of their own or as an
+/// `Option` or a `Vec`, the other types failing to compile there. They are
+/// not given the prefix of their parent, only known at runtime.
+pub fn gen_flatten_prefix(
+    attrs: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> Option<proc_macro2::TokenStream> {
+    match (prefix, attrs.prefix()) {
+        (Some(parent), Some(own)) => Some(quote!(&format!("{}{}", #parent, #own))),
+        (Some(parent), None) => Some(quote!(#parent)),
+        (None, Some(own)) => Some(quote!(#own)),
+        (None, None) => None,
+    }
+}
 
-                quote_spanned!(field.span()=> #field_name: #field_value )
+/// Generate an expression giving the prefix of the environment variables of
+/// the arguments, as an `Option<&str>`, or `None` when there is none.
+///
+/// When `env_prefix` is given, this variable holds the prefix at runtime, as
+/// combined by `gen_env_prefix_combination`. Otherwise the `env_prefix` of
+/// `parent_attribute` is used as is.
+pub fn gen_env_prefix_arg(
+    parent_attribute: &Attrs,
+    env_prefix: Option<&syn::Ident>,
+) -> Option<proc_macro2::TokenStream> {
+    match env_prefix {
+        Some(env_prefix) => Some(quote!(#env_prefix)),
+        None => parent_attribute
+            .env_prefix()
+            .map(|env_prefix| quote!(Some(#env_prefix))),
+    }
+}
+
+/// Generate the statements appending the `env_prefix` of `attrs` to the
+/// prefix given by the parent in the `env_prefix` variable.
+pub fn gen_env_prefix_combination(
+    attrs: &Attrs,
+    env_prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    match attrs.env_prefix() {
+        Some(own) => quote! {
+            let #env_prefix = match #env_prefix {
+                Some(#env_prefix) => format!("{}{}", #env_prefix, #own),
+                None => String::from(#own),
+            };
+            let #env_prefix = Some(&*#env_prefix);
+        },
+        None => quote!(),
+    }
+}
+
+/// Generate an expression turning the value of `prefix` into the prefix of
+/// an environment variable: uppercase with underscores in place of the other
+/// non-alphanumeric characters, `replica-` giving `REPLICA_`.
+pub fn gen_env_var_prefix(prefix: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        #prefix
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect::<String>()
+    }
+}
+
+/// Generate an expression giving the environment variables an argument is
+/// read from, as an `Option<Vec<String>>`, the first one set being used.
+///
+/// Returns `None` when the argument is bound to no environment variable.
+pub fn gen_env_names(
+    attrs: &Attrs,
+    ty: &Sp<Ty>,
+    parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+    env_prefix: Option<&syn::Ident>,
+) -> Option<proc_macro2::TokenStream> {
+    if attrs.has_no_env() {
+        return None;
+    }
+
+    let prefixed = |name: proc_macro2::TokenStream| match prefix {
+        Some(prefix) => {
+            let var_prefix = gen_env_var_prefix(prefix);
+            quote!(format!("{}{}", #var_prefix, #name))
+        }
+        None => quote!(::std::string::ToString::to_string(&#name)),
+    };
+
+    if let Some(names) = attrs.env_names() {
+        let names = names.iter().map(|name| prefixed(quote!(#name)));
+        return Some(quote!(Some(vec![ #( #names ),* ])));
+    }
+    if let Some(method) = attrs.find_method("env") {
+        let name = prefixed(method.args().clone());
+        return Some(quote!(Some(vec![#name])));
+    }
+
+    // flags take no value from the environment
+    let flag = match (**ty, &*attrs.parser().kind) {
+        (Ty::Bool, _) => true,
+        (_, ParserKind::FromOccurrences) | (_, ParserKind::FromFlag) => true,
+        _ => attrs.negated_name().is_some(),
+    };
+    if flag {
+        return None;
+    }
+
+    let name = attrs.env_name();
+    let name = prefixed(quote!(#name));
+    match (env_prefix, parent_attribute.env_prefix()) {
+        (Some(env_prefix), _) if attrs.is_env_only() => Some(quote! {
+            Some(vec![format!("{}{}", #env_prefix.unwrap_or(""), #name)])
+        }),
+        (Some(env_prefix), _) => Some(quote! {
+            #env_prefix.map(|env_prefix| vec![format!("{}{}", env_prefix, #name)])
+        }),
+        (None, Some(own)) => Some(quote!(Some(vec![format!("{}{}", #own, #name)]))),
+        (None, None) if attrs.is_env_only() => Some(quote!(Some(vec![#name]))),
+        (None, None) => None,
+    }
+}
+
+/// Generate an expression building a subcommand of type `ty`, giving it the
+/// prefix of the environment variables `env_prefix`.
+///
+/// The enums which are not derived ignore the prefix.
+fn gen_subcommand_value(
+    ty: &syn::Type,
+    env_prefix: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let value = gen_fallback_call(
+        ty,
+        "try_from_subcommand_with_env_prefix",
+        quote!(matches.subcommand(), #env_prefix),
+        true,
+        quote!(::clap::Subcommand),
+        quote! {
+            (
+                subcommand: (&str, Option<&::clap::ArgMatches>),
+                _env_prefix: Option<&str>
+            ) -> ::std::result::Result<Option<Self>, ::clap::Error>
+        },
+        quote!(<Self as ::clap::Subcommand>::try_from_subcommand(
+            subcommand
+        )),
+    );
+    quote!( (#value)? )
+}
+
+/// Generate a statement updating the subcommand `subcommand` of type `ty`,
+/// giving it the prefix of the environment variables `env_prefix`.
+///
+/// The enums which are not derived ignore the prefix.
+fn gen_subcommand_update(
+    ty: &syn::Type,
+    subcommand: &proc_macro2::TokenStream,
+    env_prefix: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let update = gen_fallback_call(
+        ty,
+        "try_update_from_subcommand_with_env_prefix",
+        quote!(#subcommand, matches.subcommand(), #env_prefix),
+        true,
+        quote!(::clap::Subcommand),
+        quote! {
+            (
+                &mut self,
+                subcommand: (&str, Option<&::clap::ArgMatches>),
+                _env_prefix: Option<&str>
+            ) -> ::std::result::Result<(), ::clap::Error>
+        },
+        quote!(<Self as ::clap::Subcommand>::try_update_from_subcommand(
+            self, subcommand
+        )),
+    );
+    quote!({ (#update)?; })
+}
+
+/// Generate an expression flattening a struct, either by `hidden`, given the
+/// prefix of its arguments and the prefix of their environment variables, or
+/// by `plain` through the traits when there are none.
+///
+/// `hidden` is given the prefixes returned by `gen_flatten_prefixes`. Within
+/// a variant the prefix of the environment variables is only known at
+/// runtime, so both are generated there.
+pub fn gen_flatten_dispatch(
+    flatten_prefix: Option<&proc_macro2::TokenStream>,
+    env_prefix_arg: Option<&proc_macro2::TokenStream>,
+    env_prefix: Option<&syn::Ident>,
+    plain: proc_macro2::TokenStream,
+    hidden: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match (flatten_prefix, env_prefix_arg, env_prefix) {
+        (None, None, _) => plain,
+        (None, Some(_), Some(env_prefix)) => quote! {
+            match #env_prefix {
+                Some(_) => #hidden,
+                None => #plain,
             }
+        },
+        _ => hidden,
+    }
+}
+
+/// Give the prefix of the arguments and the prefix of the environment
+/// variables passed to the hidden functions of a flattened struct, when
+/// `gen_flatten_dispatch` uses them.
+pub fn gen_flatten_prefixes(
+    flatten_prefix: Option<&proc_macro2::TokenStream>,
+    env_prefix_arg: Option<&proc_macro2::TokenStream>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    (
+        flatten_prefix.cloned().unwrap_or_else(|| quote!("")),
+        env_prefix_arg.cloned().unwrap_or_else(|| quote!(None)),
+    )
+}
+
+/// Generate a call to the hidden inherent function `func` of `ty`, passing
+/// it `args`.
+///
+/// Only derived types have the inherent function. When `fallback` is set, the
+/// other types implementing `bound` get a trait method of the same name, with
+/// the given `signature` and `body`, the inherent function being picked first
+/// by the method lookup. Otherwise they are rejected at compile time.
+pub fn gen_fallback_call(
+    ty: &syn::Type,
+    func: &str,
+    args: proc_macro2::TokenStream,
+    fallback: bool,
+    bound: proc_macro2::TokenStream,
+    signature: proc_macro2::TokenStream,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let trait_name = syn::Ident::new(&func.to_camel_case(), ty.span());
+    let func = syn::Ident::new(func, ty.span());
+    if !fallback {
+        return quote_spanned!(ty.span()=> <#ty>::#func(#args));
+    }
+
+    quote_spanned! { ty.span()=>
+        {
+            #[allow(dead_code)]
+            trait #trait_name: #bound {
+                fn #func #signature {
+                    #body
+                }
+            }
+            impl<T: #bound> #trait_name for T {}
+
+            <#ty>::#func(#args)
         }
-    });
+    }
+}
 
-    quote! {{
-        #( #fields ),*
-    }}
+/// Generate an expression building a flattened struct of type `ty` whose
+/// arguments are prefixed by `prefix`, see `gen_fallback_call` for
+/// `fallback`.
+pub fn gen_flattened_value(
+    ty: &syn::Type,
+    prefix: &proc_macro2::TokenStream,
+    env_prefix: &proc_macro2::TokenStream,
+    fallback: bool,
+) -> proc_macro2::TokenStream {
+    let value = gen_fallback_call(
+        ty,
+        "try_from_argmatches_with_prefix",
+        quote!(matches, #prefix, #env_prefix),
+        fallback,
+        quote!(::clap::FromArgMatches),
+        quote! {
+            (
+                matches: &::clap::ArgMatches,
+                _prefix: &str,
+                _env_prefix: Option<&str>
+            ) -> ::std::result::Result<Self, ::clap::Error>
+        },
+        quote!(<Self as ::clap::FromArgMatches>::try_from_argmatches(
+            matches
+        )),
+    );
+    quote!( (#value)? )
+}
+
+/// Generate a statement updating the flattened struct of type `ty` at
+/// `place`, whose arguments are prefixed by `prefix`, see
+/// `gen_fallback_call` for `fallback`.
+pub fn gen_flattened_update(
+    ty: &syn::Type,
+    place: &proc_macro2::TokenStream,
+    prefix: &proc_macro2::TokenStream,
+    env_prefix: &proc_macro2::TokenStream,
+    fallback: bool,
+) -> proc_macro2::TokenStream {
+    let update = gen_fallback_call(
+        ty,
+        "try_update_from_argmatches_with_prefix",
+        quote!(&mut #place, matches, #prefix, #env_prefix),
+        fallback,
+        quote!(::clap::FromArgMatches),
+        quote! {
+            (
+                &mut self,
+                matches: &::clap::ArgMatches,
+                _prefix: &str,
+                _env_prefix: Option<&str>
+            ) -> ::std::result::Result<(), ::clap::Error>
+        },
+        quote!(<Self as ::clap::FromArgMatches>::try_update_from_argmatches(self, matches)),
+    );
+    quote!({ (#update)?; })
 }
 
-pub fn gen_from_argmatches_impl_for_enum(name: &syn::Ident) -> proc_macro2::TokenStream {
+/// Generate an expression parsing the value of an argument from `matches`.
+///
+/// The value of an argument bound to several environment variables, or of an
+/// `env_only` field, is read from the first of them which is set when the
+/// argument is absent from the command line, clap only knowing of the first
+/// one. An `env_only` field falls back on its default value.
+fn gen_arg_value(
+    attrs: &Attrs,
+    ty: &Sp<Ty>,
+    parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+    env_prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    use self::ParserKind::*;
+
+    let name = gen_arg_id(&attrs.cased_name(), prefix);
+    let parser = attrs.parser();
+    let func = &parser.func;
+    let span = parser.kind.span();
+    let invalid_value = quote_spanned! { func.span()=>
+        |err| ::clap::Error::with_description(
+            &format!("Invalid value for '{}': {}", #name, err),
+            ::clap::ErrorKind::ValueValidation
+        )
+    };
+    let invalid_utf8 = quote! {
+        ::clap::Error::with_description(
+            &format!(
+                "Invalid UTF-8 was detected in the environment variable '{}'",
+                env_name
+            ),
+            ::clap::ErrorKind::InvalidUtf8
+        )
+    };
+    let (value_of, values_of, parse, env_value) = match *parser.kind {
+        FromStr => (
+            quote_spanned!(span=> value_of),
+            quote_spanned!(span=> values_of),
+            quote_spanned!(func.span()=> |s| Ok::<_, ::clap::Error>(#func(s))),
+            quote!(env_value.to_str().ok_or_else(|| #invalid_utf8)?),
+        ),
+        TryFromStr => (
+            quote_spanned!(span=> value_of),
+            quote_spanned!(span=> values_of),
+            quote_spanned!(func.span()=> |s| #func(s).map_err(#invalid_value)),
+            quote!(env_value.to_str().ok_or_else(|| #invalid_utf8)?),
+        ),
+        FromOsStr => (
+            quote_spanned!(span=> value_of_os),
+            quote_spanned!(span=> values_of_os),
+            quote_spanned!(func.span()=> |s| Ok::<_, ::clap::Error>(#func(s))),
+            quote!(&*env_value),
+        ),
+        TryFromOsStr => (
+            quote_spanned!(span=> value_of_os),
+            quote_spanned!(span=> values_of_os),
+            quote_spanned!(func.span()=> |s| #func(s).map_err(#invalid_value)),
+            quote!(&*env_value),
+        ),
+        FromOccurrences => (
+            quote_spanned!(span=> occurrences_of),
+            quote!(),
+            func.clone(),
+            quote!(),
+        ),
+        FromFlag => (quote!(), quote!(), func.clone(), quote!()),
+    };
+
+    let flag = *attrs.parser().kind == ParserKind::FromFlag;
+    let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
+    let env_only = attrs.is_env_only();
+    let env_names = match attrs.env_names() {
+        Some(names) if names.len() > 1 && !flag && !occurrences && **ty != Ty::Bool => {
+            gen_env_names(attrs, ty, parent_attribute, prefix, env_prefix)
+        }
+        _ if env_only => gen_env_names(attrs, ty, parent_attribute, prefix, env_prefix),
+        // clap gives the rendered `default_value_t` like a value taken from
+        // the environment, so the variables are read here
+        _ if attrs.default_value_t().is_some() => {
+            gen_env_names(attrs, ty, parent_attribute, prefix, env_prefix)
+        }
+        _ => None,
+    };
+
+    // an `env_only` field has no argument in `matches`, only its default value
+    let value = if env_only {
+        let default = attrs
+            .find_method("default_value")
+            .map(|method| method.args());
+        match *parser.kind {
+            FromOsStr | TryFromOsStr => match default {
+                Some(default) => quote!(Some(::std::ffi::OsStr::new(#default))),
+                None => quote!(None::<&::std::ffi::OsStr>),
+            },
+            _ => match default {
+                Some(default) => quote!(Some(#default)),
+                None => quote!(None::<&str>),
+            },
+        }
+    } else {
+        quote_spanned!(ty.span()=> matches.#value_of(#name))
+    };
+    let missing = if env_only {
+        quote! {
+            ::clap::Error::with_description(
+                &format!(
+                    "The environment variable '{}' is required but not set",
+                    env_names[0]
+                ),
+                ::clap::ErrorKind::MissingRequiredArgument
+            )
+        }
+    } else {
+        quote! {
+            ::clap::Error::with_description(
+                &format!(
+                    "The following required argument was not provided: '{}'",
+                    #name
+                ),
+                ::clap::ErrorKind::MissingRequiredArgument
+            )
+        }
+    };
+
+    let from_matches = match **ty {
+        Ty::Bool => quote_spanned! { ty.span()=>
+            matches.is_present(#name)
+        },
+
+        Ty::Option if attrs.negated_name().is_some() => {
+            let negated = gen_arg_id(&attrs.negated_name().unwrap(), prefix);
+            quote_spanned! { ty.span()=>
+                if matches.is_present(#name) {
+                    Some(true)
+                } else if matches.is_present(#negated) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+        }
+
+        Ty::Option => quote_spanned! { ty.span()=>
+            #value
+                .map(#parse)
+                .transpose()?
+        },
+
+        Ty::OptionOption => quote_spanned! { ty.span()=>
+            if matches.is_present(#name) {
+                Some(matches.#value_of(#name).map(#parse).transpose()?)
+            } else {
+                None
+            }
+        },
+
+        Ty::OptionVec => quote_spanned! { ty.span()=>
+            if matches.is_present(#name) {
+                Some(matches.#values_of(#name)
+                     .map(|v| v.map(#parse).collect::<::std::result::Result<Vec<_>, _>>())
+                     .transpose()?
+                     .unwrap_or_else(Vec::new))
+            } else {
+                None
+            }
+        },
+
+        Ty::Vec => quote_spanned! { ty.span()=>
+            matches.#values_of(#name)
+                .map(|v| v.map(#parse).collect::<::std::result::Result<Vec<_>, _>>())
+                .transpose()?
+                .unwrap_or_else(Vec::new)
+        },
+
+        Ty::Other if occurrences => quote_spanned! { ty.span()=>
+            #parse(matches.#value_of(#name))
+        },
+
+        Ty::Other if flag => quote_spanned! { ty.span()=>
+            #parse(matches.is_present(#name))
+        },
+
+        Ty::Other => {
+            let value = quote_spanned! { ty.span()=>
+                #value
+                    .ok_or_else(|| #missing)
+                    .and_then(#parse)?
+            };
+            match attrs.default_value_t() {
+                Some(default) if env_only => default.clone(),
+                // the environment variables set are read above
+                Some(default) => quote_spanned! { ty.span()=>
+                    if matches.occurrences_of(#name) == 0 {
+                        #default
+                    } else {
+                        #value
+                    }
+                },
+                _ => value,
+            }
+        }
+    };
+
+    let env_names = match env_names {
+        Some(env_names) => env_names,
+        None => return from_matches,
+    };
+    let from_env = quote!( (#parse)(#env_value)? );
+    let from_env = match **ty {
+        Ty::Option => quote!(Some(#from_env)),
+        Ty::OptionOption => quote!(Some(Some(#from_env))),
+        Ty::OptionVec => quote!(Some(vec![#from_env])),
+        Ty::Vec => quote!(vec![#from_env]),
+        _ => from_env,
+    };
+    let found = quote! {
+        env_names
+            .iter()
+            .filter_map(|name| ::std::env::var_os(name).map(|value| (name, value)))
+            .next()
+    };
+    let found = if env_only {
+        found
+    } else {
+        quote! {
+            if matches.occurrences_of(#name) == 0 {
+                #found
+            } else {
+                None
+            }
+        }
+    };
+    quote_spanned! { ty.span()=>
+        {
+            let env_names: Vec<String> = (#env_names).unwrap_or_else(Vec::new);
+            match #found {
+                Some((env_name, env_value)) => #from_env,
+                None => #from_matches,
+            }
+        }
+    }
+}
+
+pub fn gen_from_argmatches_impl_for_enum(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let missing = gen_missing_subcommand_error();
+    let from_subcommand = gen_from_subcommand(name, variants, parent_attribute);
+    let update_from_subcommand = gen_update_from_subcommand(
+        name,
+        variants,
+        parent_attribute,
+        &quote!(Self::try_from_subcommand_with_env_prefix(
+            subcommand, env_prefix
+        )),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     quote! {
-        impl ::clap::FromArgMatches for #name {
+        impl #impl_generics ::clap::FromArgMatches for #name #ty_generics #where_clause {
             fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
-                <#name>::from_subcommand(matches.subcommand())
-                    .unwrap()
+                <Self as ::clap::FromArgMatches>::try_from_argmatches(matches)
+                    .unwrap_or_else(|e| panic!("{}", e))
+            }
+
+            fn try_from_argmatches(
+                matches: &::clap::ArgMatches
+            ) -> ::std::result::Result<Self, ::clap::Error> {
+                Self::try_from_subcommand_with_env_prefix(matches.subcommand(), None)?
+                    .ok_or_else(|| #missing)
+            }
+
+            fn update_from_argmatches(&mut self, matches: &::clap::ArgMatches) {
+                <Self as ::clap::FromArgMatches>::try_update_from_argmatches(self, matches)
+                    .unwrap_or_else(|e| panic!("{}", e))
+            }
+
+            fn try_update_from_argmatches(
+                &mut self,
+                matches: &::clap::ArgMatches
+            ) -> ::std::result::Result<(), ::clap::Error> {
+                self.try_update_from_subcommand_with_env_prefix(matches.subcommand(), None)
             }
         }
 
-        impl From<::clap::ArgMatches> for #name {
+        impl #impl_generics From<::clap::ArgMatches> for #name #ty_generics #where_clause {
             fn from(m: ::clap::ArgMatches) -> Self {
                 use ::clap::FromArgMatches;
                 <Self as ::clap::FromArgMatches>::from_argmatches(&m)
@@ -233,5 +1348,27 @@ pub fn gen_from_argmatches_impl_for_enum(name: &syn::Ident) -> proc_macro2::Toke
         }
 
         // @TODO: impl TryFrom once stable
+
+        #[allow(unused_variables, unreachable_code)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc(hidden)]
+            #[allow(dead_code, unreachable_pub)]
+            pub fn try_from_subcommand_with_env_prefix(
+                subcommand: (&str, Option<&::clap::ArgMatches>),
+                env_prefix: Option<&str>
+            ) -> ::std::result::Result<Option<Self>, ::clap::Error> {
+                #from_subcommand
+            }
+
+            #[doc(hidden)]
+            #[allow(dead_code, unreachable_pub)]
+            pub fn try_update_from_subcommand_with_env_prefix(
+                &mut self,
+                subcommand: (&str, Option<&::clap::ArgMatches>),
+                env_prefix: Option<&str>
+            ) -> ::std::result::Result<(), ::clap::Error> {
+                #update_from_subcommand
+            }
+        }
     }
 }
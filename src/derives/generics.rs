@@ -0,0 +1,115 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generic parameters handling
+
+use proc_macro2;
+use syn::{self, parse_quote};
+
+use super::{is_simple_ty, named_fields, spanned::Sp, sub_type, Attrs, Kind, Ty, DEFAULT_CASING};
+
+/// Get the generics of `input` with the trait bounds needed to parse its
+/// type parameters.
+///
+/// A type parameter used as an argument with the default parser, like
+/// `value: T` or `values: Vec<T>`, gets `T: FromStr` and `T::Err: Display`.
+/// A flattened one gets `T: Args + FromArgMatches` and a subcommand one
+/// `T: Subcommand`. Any other use is left to the bounds written by the user.
+pub fn bounded_generics(input: &syn::DeriveInput) -> syn::Generics {
+    use syn::Data::*;
+
+    let mut generics = input.generics.clone();
+    let params: Vec<syn::Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    if params.is_empty() {
+        return generics;
+    }
+
+    let fields: Vec<syn::Field> = match input.data {
+        Struct(ref data) => named_fields(&data.fields, &input.ident)
+            .into_iter()
+            .collect(),
+        Enum(ref data) => data
+            .variants
+            .iter()
+            .filter(|variant| match variant.fields {
+                // delegates to the inner type, which carries its own bounds
+                syn::Fields::Unnamed(ref fields) => fields.unnamed.len() > 1,
+                _ => true,
+            })
+            .flat_map(|variant| named_fields(&variant.fields, &variant.ident))
+            .collect(),
+        Union(_) => vec![],
+    };
+
+    let mut predicates: Vec<proc_macro2::TokenStream> = vec![];
+    for field in &fields {
+        let attrs = Attrs::from_field(field, Sp::call_site(DEFAULT_CASING));
+        let (ty, bounds) = match *attrs.kind() {
+            Kind::Arg(ref ty) if !attrs.has_custom_parser() => {
+                let parsed_ty = match **ty {
+                    Ty::Bool => continue,
+                    Ty::Vec | Ty::Option => sub_type(&field.ty),
+                    Ty::OptionOption | Ty::OptionVec => sub_type(&field.ty).and_then(sub_type),
+                    Ty::Other => Some(&field.ty),
+                };
+                (parsed_ty, quote!(::std::str::FromStr))
+            }
+            Kind::FlattenStruct(ref ty) => {
+                let flattened_ty = match **ty {
+                    Ty::Option | Ty::Vec => sub_type(&field.ty),
+                    _ => Some(&field.ty),
+                };
+                (flattened_ty, quote!(::clap::Args + ::clap::FromArgMatches))
+            }
+            Kind::Subcommand(ref ty) => {
+                let subcmd_ty = match **ty {
+                    Ty::Option => sub_type(&field.ty),
+                    _ => Some(&field.ty),
+                };
+                (subcmd_ty, quote!(::clap::Subcommand))
+            }
+            _ => continue,
+        };
+
+        let param = ty.and_then(|ty| {
+            params
+                .iter()
+                .find(|param| is_simple_ty(ty, &param.to_string()))
+        });
+        if let Some(param) = param {
+            let mut new_predicates = vec![quote!(#param: #bounds)];
+            if let Kind::Arg(_) = *attrs.kind() {
+                new_predicates.push(quote! {
+                    <#param as ::std::str::FromStr>::Err: ::std::fmt::Display
+                });
+            }
+            for predicate in new_predicates {
+                if !predicates
+                    .iter()
+                    .any(|p| p.to_string() == predicate.to_string())
+                {
+                    predicates.push(predicate);
+                }
+            }
+        }
+    }
+
+    if !predicates.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for predicate in predicates {
+            where_clause.predicates.push(parse_quote!(#predicate));
+        }
+    }
+
+    generics
+}
@@ -14,40 +14,68 @@
 use std::env;
 
 use proc_macro2;
-use syn;
-
-use super::{spanned::Sp, Attrs, GenOutput, Name, DEFAULT_CASING};
+use proc_macro_error::abort_call_site;
+use syn::{self, parse_quote, punctuated, token};
+
+use super::{
+    bounded_generics, check_attrs,
+    clap::{
+        gen_augment_args_fn, gen_augment_args_with_prefix_fn, gen_augment_subcommands,
+        gen_from_subcommand, gen_has_subcommand, gen_update_from_subcommand,
+    },
+    crate_path,
+    spanned::Sp,
+    with_crate_path, Attrs, GenOutput, Name, DEFAULT_CASING,
+};
 
 pub fn derive_into_app(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     use syn::Data::*;
 
     let struct_name = &input.ident;
+    check_attrs(input);
+    let generics = bounded_generics(input);
     let inner_impl = match input.data {
-        Struct(syn::DataStruct { .. }) => {
-            gen_into_app_impl_for_struct(struct_name, &input.attrs).tokens
+        Struct(syn::DataStruct { ref fields, .. }) => {
+            gen_into_app_impl_for_struct(struct_name, &generics, fields, &input.attrs).tokens
+        }
+        Enum(ref e) => {
+            gen_into_app_impl_for_enum(struct_name, &generics, &e.variants, &input.attrs).tokens
         }
-        // @TODO impl into_app for enums?
-        // Enum(ref e) => clap_for_enum_impl(struct_name, &e.variants, &input.attrs),
-        _ => panic!("clap_derive only supports non-tuple structs"), // and enums"),
+        _ => abort_call_site!("clap_derive only supports structs and enums"),
     };
 
-    quote!(#inner_impl)
+    with_crate_path(inner_impl, crate_path(&input.attrs).as_ref())
 }
 
-pub fn gen_into_app_impl_for_struct(name: &syn::Ident, attrs: &[syn::Attribute]) -> GenOutput {
-    let into_app_fn = gen_into_app_fn_for_struct(attrs);
+pub fn gen_into_app_impl_for_struct(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    fields: &syn::Fields,
+    attrs: &[syn::Attribute],
+) -> GenOutput {
+    let into_app_fn = gen_into_app_fn(attrs);
     let into_app_fn_tokens = into_app_fn.tokens;
+    let augment_args_fn = gen_augment_args_fn(name, fields, &into_app_fn.attrs);
+    let augment_args_with_prefix_fn =
+        gen_augment_args_with_prefix_fn(name, fields, &into_app_fn.attrs);
+    let into_impl = gen_into_impl(name, generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let tokens = quote! {
-        impl ::clap::IntoApp for #name {
+        impl #impl_generics ::clap::IntoApp for #name #ty_generics #where_clause {
             #into_app_fn_tokens
         }
 
-        impl<'b> Into<::clap::App<'b>> for #name {
-            fn into(self) -> ::clap::App<'b> {
-                use ::clap::IntoApp;
-                <#name as ::clap::IntoApp>::into_app()
-            }
+        #into_impl
+
+        #[allow(unreachable_code)]
+        impl #impl_generics ::clap::Args for #name #ty_generics #where_clause {
+            #augment_args_fn
+        }
+
+        #[allow(unreachable_code)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            #augment_args_with_prefix_fn
         }
     };
 
@@ -57,13 +85,13 @@ pub fn gen_into_app_impl_for_struct(name: &syn::Ident, attrs: &[syn::Attribute])
     }
 }
 
-pub fn gen_into_app_fn_for_struct(struct_attrs: &[syn::Attribute]) -> GenOutput {
-    let gen = gen_app_builder(struct_attrs);
+pub fn gen_into_app_fn(attrs: &[syn::Attribute]) -> GenOutput {
+    let gen = gen_app_builder(attrs);
     let app_tokens = gen.tokens;
 
     let tokens = quote! {
-        fn into_app<'b>() -> ::clap::App<'b> {
-            Self::augment_app(#app_tokens)
+        fn into_app<'__clap_b>() -> ::clap::App<'__clap_b> {
+            <Self as ::clap::Args>::augment_args(#app_tokens)
         }
     };
 
@@ -82,6 +110,7 @@ pub fn gen_app_builder(attrs: &[syn::Attribute]) -> GenOutput {
         Name::Assigned(syn::LitStr::new(&name, proc_macro2::Span::call_site())),
         Sp::call_site(DEFAULT_CASING),
     );
+    // `external_subcommand` is reported by `check_attrs`
     let tokens = {
         let name = attrs.cased_name();
         quote!(::clap::App::new(#name))
@@ -90,19 +119,93 @@ pub fn gen_app_builder(attrs: &[syn::Attribute]) -> GenOutput {
     GenOutput { tokens, attrs }
 }
 
-pub fn gen_into_app_impl_for_enum(name: &syn::Ident, attrs: &[syn::Attribute]) -> GenOutput {
-    let into_app_fn = gen_into_app_fn_for_enum(attrs);
+pub fn gen_into_app_impl_for_enum(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    attrs: &[syn::Attribute],
+) -> GenOutput {
+    let into_app_fn = gen_into_app_fn(attrs);
     let into_app_fn_tokens = into_app_fn.tokens;
+    let augment_subcommands = gen_augment_subcommands(variants, &into_app_fn.attrs);
+    let from_subcommand = gen_from_subcommand(name, variants, &into_app_fn.attrs);
+    let update_from_subcommand = gen_update_from_subcommand(
+        name,
+        variants,
+        &into_app_fn.attrs,
+        &quote!(<Self as ::clap::Subcommand>::try_from_subcommand(
+            subcommand
+        )),
+    );
+    let has_subcommand = gen_has_subcommand(variants, &into_app_fn.attrs);
+    let into_impl = gen_into_impl(name, generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let tokens = quote! {
-        impl ::clap::IntoApp for #name {
+        impl #impl_generics ::clap::IntoApp for #name #ty_generics #where_clause {
             #into_app_fn_tokens
         }
 
-        impl<'b> Into<::clap::App<'b>> for #name {
-            fn into(self) -> ::clap::App<'b> {
-                use ::clap::IntoApp;
-                <#name as ::clap::IntoApp>::into_app()
+        #into_impl
+
+        #[allow(unused_variables, unreachable_code)]
+        impl #impl_generics ::clap::Args for #name #ty_generics #where_clause {
+            fn augment_args<'__clap_b>(app: ::clap::App<'__clap_b>) -> ::clap::App<'__clap_b> {
+                Self::augment_subcommands_with_env_prefix(app, None)
+                    .setting(::clap::AppSettings::SubcommandRequiredElseHelp)
+            }
+        }
+
+        // the variants are matched here too, so that a hand-written
+        // FromArgMatches still leaves the enum usable as a subcommand
+        #[allow(unused_variables, unreachable_code)]
+        impl #impl_generics ::clap::Subcommand for #name #ty_generics #where_clause {
+            fn augment_subcommands<'__clap_b>(app: ::clap::App<'__clap_b>) -> ::clap::App<'__clap_b> {
+                Self::augment_subcommands_with_env_prefix(app, None)
+            }
+
+            fn from_subcommand(
+                subcommand: (&str, Option<&::clap::ArgMatches>)
+            ) -> Option<Self> {
+                <Self as ::clap::Subcommand>::try_from_subcommand(subcommand)
+                    .unwrap_or_else(|e| panic!("{}", e))
+            }
+
+            fn try_from_subcommand(
+                subcommand: (&str, Option<&::clap::ArgMatches>)
+            ) -> ::std::result::Result<Option<Self>, ::clap::Error> {
+                let env_prefix: Option<&str> = None;
+                #from_subcommand
+            }
+
+            fn update_from_subcommand(
+                &mut self,
+                subcommand: (&str, Option<&::clap::ArgMatches>)
+            ) {
+                <Self as ::clap::Subcommand>::try_update_from_subcommand(self, subcommand)
+                    .unwrap_or_else(|e| panic!("{}", e))
+            }
+
+            fn try_update_from_subcommand(
+                &mut self,
+                subcommand: (&str, Option<&::clap::ArgMatches>)
+            ) -> ::std::result::Result<(), ::clap::Error> {
+                let env_prefix: Option<&str> = None;
+                #update_from_subcommand
+            }
+
+            #has_subcommand
+        }
+
+        #[allow(unused_variables, unreachable_code)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc(hidden)]
+            #[allow(dead_code, unreachable_pub)]
+            pub fn augment_subcommands_with_env_prefix<'__clap_b>(
+                app: ::clap::App<'__clap_b>,
+                env_prefix: Option<&str>
+            ) -> ::clap::App<'__clap_b> {
+                #augment_subcommands
             }
         }
     };
@@ -113,20 +216,20 @@ pub fn gen_into_app_impl_for_enum(name: &syn::Ident, attrs: &[syn::Attribute]) -
     }
 }
 
-pub fn gen_into_app_fn_for_enum(enum_attrs: &[syn::Attribute]) -> GenOutput {
-    let gen = gen_app_builder(enum_attrs);
-    let app_tokens = gen.tokens;
-
-    let tokens = quote! {
-        fn into_app<'b>() -> ::clap::App<'b> {
-            let app = #app_tokens
-                .setting(::clap::AppSettings::SubcommandRequiredElseHelp);
-            Self::augment_app(app)
+/// Generate the `Into<App>` impl, adding the lifetime of the `App` to the
+/// generics of the type under a name that can't clash with the type's own.
+fn gen_into_impl(name: &syn::Ident, generics: &syn::Generics) -> proc_macro2::TokenStream {
+    let mut app_generics = generics.clone();
+    app_generics.params.insert(0, parse_quote!('__clap_b));
+    let (impl_generics, _, _) = app_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics Into<::clap::App<'__clap_b>> for #name #ty_generics #where_clause {
+            fn into(self) -> ::clap::App<'__clap_b> {
+                use ::clap::IntoApp;
+                <Self as ::clap::IntoApp>::into_app()
+            }
         }
-    };
-
-    GenOutput {
-        tokens,
-        attrs: gen.attrs,
     }
 }
@@ -0,0 +1,232 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Known builder methods of `clap::Arg` and `clap::App`
+//!
+//! Any `name = value` or `name(args...)` attribute which isn't handled by
+//! clap_derive itself becomes a call to the builder method `name`. These
+//! lists are used to catch typos and misplaced methods before they turn
+//! into "no method named ..." errors in the generated code.
+
+/// The builder methods of `clap::Arg`, with the number of arguments they take.
+pub const ARG_METHODS: &[(&str, usize)] = &[
+    ("about", 1),
+    ("alias", 1),
+    ("aliases", 1),
+    ("allow_hyphen_values", 1),
+    ("case_insensitive", 1),
+    ("conflicts_with", 1),
+    ("conflicts_with_all", 1),
+    ("default_missing_value", 1),
+    ("default_value", 1),
+    ("default_value_if", 3),
+    ("default_value_if_os", 3),
+    ("default_value_ifs", 1),
+    ("default_value_ifs_os", 1),
+    ("default_value_os", 1),
+    ("default_values", 1),
+    ("default_values_os", 1),
+    ("display_order", 1),
+    ("empty_values", 1),
+    ("env", 1),
+    ("env_os", 1),
+    ("exclusive", 1),
+    ("forbid_empty_values", 1),
+    ("global", 1),
+    ("group", 1),
+    ("groups", 1),
+    ("help", 1),
+    ("help_heading", 1),
+    ("hidden", 1),
+    ("hidden_long_help", 1),
+    ("hidden_short_help", 1),
+    ("hide_default_value", 1),
+    ("hide_env_values", 1),
+    ("hide_possible_values", 1),
+    ("index", 1),
+    ("last", 1),
+    ("long", 1),
+    ("long_about", 1),
+    ("long_help", 1),
+    ("max_values", 1),
+    ("min_values", 1),
+    ("multiple", 1),
+    ("multiple_occurrences", 1),
+    ("multiple_values", 1),
+    ("next_line_help", 1),
+    ("number_of_values", 1),
+    ("overrides_with", 1),
+    ("overrides_with_all", 1),
+    ("possible_value", 1),
+    ("possible_values", 1),
+    ("raw", 1),
+    ("require_delimiter", 1),
+    ("require_equals", 1),
+    ("required", 1),
+    ("required_if", 2),
+    ("required_if_eq", 2),
+    ("required_if_eq_all", 1),
+    ("required_if_eq_any", 1),
+    ("required_ifs", 1),
+    ("required_unless", 1),
+    ("required_unless_all", 1),
+    ("required_unless_one", 1),
+    ("required_unless_present", 1),
+    ("required_unless_present_all", 1),
+    ("required_unless_present_any", 1),
+    ("requires", 1),
+    ("requires_all", 1),
+    ("requires_if", 2),
+    ("requires_ifs", 1),
+    ("set", 1),
+    ("setting", 1),
+    ("short", 1),
+    ("short_alias", 1),
+    ("short_aliases", 1),
+    ("takes_value", 1),
+    ("unset", 1),
+    ("unset_setting", 1),
+    ("use_delimiter", 1),
+    ("validator", 1),
+    ("validator_os", 1),
+    ("value_delimiter", 1),
+    ("value_hint", 1),
+    ("value_name", 1),
+    ("value_names", 1),
+    ("value_terminator", 1),
+    ("visible_alias", 1),
+    ("visible_aliases", 1),
+    ("visible_short_alias", 1),
+    ("visible_short_aliases", 1),
+];
+
+/// The builder methods of `clap::App`, with the number of arguments they take.
+pub const APP_METHODS: &[(&str, usize)] = &[
+    ("about", 1),
+    ("after_help", 1),
+    ("after_long_help", 1),
+    ("alias", 1),
+    ("aliases", 1),
+    ("arg", 1),
+    ("args", 1),
+    ("author", 1),
+    ("before_help", 1),
+    ("before_long_help", 1),
+    ("bin_name", 1),
+    ("display_order", 1),
+    ("global_setting", 1),
+    ("global_settings", 1),
+    ("group", 1),
+    ("groups", 1),
+    ("help_heading", 1),
+    ("help_message", 1),
+    ("help_template", 1),
+    ("long_about", 1),
+    ("long_flag", 1),
+    ("long_flag_alias", 1),
+    ("long_flag_aliases", 1),
+    ("long_version", 1),
+    ("max_term_width", 1),
+    ("mut_arg", 2),
+    ("override_help", 1),
+    ("override_usage", 1),
+    ("replace", 2),
+    ("setting", 1),
+    ("settings", 1),
+    ("short_flag", 1),
+    ("short_flag_alias", 1),
+    ("short_flag_aliases", 1),
+    ("stop_custom_headings", 0),
+    ("subcommand", 1),
+    ("subcommands", 1),
+    ("template", 1),
+    ("term_width", 1),
+    ("unset_global_setting", 1),
+    ("unset_setting", 1),
+    ("unset_settings", 1),
+    ("version", 1),
+    ("version_message", 1),
+    ("visible_alias", 1),
+    ("visible_aliases", 1),
+    ("visible_long_flag_alias", 1),
+    ("visible_long_flag_aliases", 1),
+    ("visible_short_flag_alias", 1),
+    ("visible_short_flag_aliases", 1),
+];
+
+/// The `name = value` and `name(...)` attributes of clap_derive allowed on
+/// fields, which aren't builder methods.
+pub const FIELD_ATTRS: &[&str] = &["default_value_t", "env", "name", "parse", "prefix", "skip"];
+
+/// The `name = value` attributes of clap_derive allowed on structs, enums
+/// and variants, which aren't builder methods.
+pub const STRUCT_ATTRS: &[&str] = &[
+    "about",
+    "author",
+    "crate",
+    "env_prefix",
+    "name",
+    "rename_all",
+    "version",
+];
+
+/// The attributes of clap_derive made of a sole identifier.
+pub const FLAG_ATTRS: &[&str] = &[
+    "about",
+    "author",
+    "default_value_t",
+    "derive_display_order",
+    "env_only",
+    "external_subcommand",
+    "flatten",
+    "help_heading",
+    "long",
+    "negatable",
+    "no_env",
+    "no_version",
+    "repeated",
+    "short",
+    "skip",
+    "subcommand",
+    "verbatim_doc_comment",
+];
+
+/// Find the closest of the `candidates` to `name`, if any is close enough
+/// to be a plausible typo.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = std::cmp::max(name.chars().count() / 3, 1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
@@ -18,13 +18,23 @@ pub mod attrs;
 pub mod parse;
 pub mod spanned;
 pub mod ty;
+mod check;
 mod clap;
+mod crate_path;
+mod doc_comments;
 mod from_argmatches;
+mod generics;
 mod into_app;
+mod methods;
 
 pub use self::arg_enum::derive_arg_enum;
-pub use self::attrs::{Attrs, Kind, Name, Parser, ParserKind, CasingStyle, GenOutput, DEFAULT_CASING};
-pub use self::ty::{sub_type, Ty};
+pub use self::attrs::{
+    named_fields, Attrs, CasingStyle, GenOutput, Kind, Name, Parser, ParserKind, DEFAULT_CASING,
+};
+pub use self::ty::{is_simple_ty, sub_type, Ty};
+pub use self::check::check_attrs;
 pub use self::clap::derive_clap;
+pub use self::crate_path::{crate_path, with_crate_path};
 pub use self::from_argmatches::derive_from_argmatches;
+pub use self::generics::bounded_generics;
 pub use self::into_app::derive_into_app;
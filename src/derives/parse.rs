@@ -1,28 +1,67 @@
 use std::iter::FromIterator;
 
-use proc_macro2::TokenStream;
-use proc_macro_error::{abort, ResultExt};
+use proc_macro2::{TokenStream, TokenTree};
+use proc_macro_error::emit_error;
 use syn::{
-    self, parenthesized,
-    parse::{Parse, ParseBuffer, ParseStream},
+    self,
+    ext::IdentExt,
+    parenthesized,
+    parse::{discouraged::Speculative, Parse, ParseStream, Parser},
     parse2,
     punctuated::Punctuated,
     spanned::Spanned,
     Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitStr, Token,
 };
 
+use super::methods::{closest, FLAG_ATTRS};
+
 pub struct ClapAttributes {
     pub paren_token: syn::token::Paren,
-    pub attrs: Punctuated<ClapAttr, Token![,]>,
+    pub attrs: Vec<ClapAttr>,
+    pub invalid: bool,
 }
 
 impl Parse for ClapAttributes {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let content;
         let paren_token = parenthesized!(content in input);
-        let attrs = content.parse_terminated(ClapAttr::parse)?;
 
-        Ok(ClapAttributes { paren_token, attrs })
+        // an invalid attribute is reported and skipped, so the errors in the
+        // following ones are reported as well
+        let mut attrs = Vec::new();
+        let mut invalid = false;
+        while !content.is_empty() {
+            // parsed in a fork, so the tokens left in a nested group after an
+            // error don't make the whole `#[clap(...)]` invalid
+            let fork = content.fork();
+            match ClapAttr::parse(&fork) {
+                Ok(attr) => {
+                    content.advance_to(&fork);
+                    invalid |= attr.is_none();
+                    attrs.extend(attr);
+                }
+                Err(e) => {
+                    emit_error!(e);
+                    invalid = true;
+                    skip_until_comma(&content)?;
+                }
+            }
+
+            if !content.is_empty() {
+                if let Err(e) = content.parse::<Token![,]>() {
+                    emit_error!(e);
+                    invalid = true;
+                    skip_until_comma(&content)?;
+                    let _: Option<Token![,]> = content.parse()?;
+                }
+            }
+        }
+
+        Ok(ClapAttributes {
+            paren_token,
+            attrs,
+            invalid,
+        })
     }
 }
 
@@ -31,8 +70,16 @@ pub enum ClapAttr {
     Short(Ident),
     Long(Ident),
     Flatten(Ident),
+    Repeated(Ident),
+    Negatable(Ident),
+    NoEnv(Ident),
+    VerbatimDocComment(Ident),
+    EnvOnly(Ident),
     Subcommand(Ident),
+    ExternalSubcommand(Ident),
     NoVersion(Ident),
+    DeriveDisplayOrder(Ident),
+    HelpHeading(Ident),
 
     // ident [= "string literal"]
     About(Ident, Option<LitStr>),
@@ -41,6 +88,9 @@ pub enum ClapAttr {
     // ident = "string literal"
     Version(Ident, LitStr),
     RenameAll(Ident, LitStr),
+    Prefix(Ident, LitStr),
+    EnvPrefix(Ident, LitStr),
+    Crate(Ident, LitStr),
     NameLitStr(Ident, LitStr),
 
     // parse(parser_kind [= parser_func])
@@ -48,19 +98,26 @@ pub enum ClapAttr {
 
     // ident [= arbitrary_expr]
     Skip(Ident, Option<Expr>),
+    DefaultValueT(Ident, Option<Expr>),
 
     // ident = arbitrary_expr
     NameExpr(Ident, Expr),
 
+    // env = [arbitrary_expr,*]
+    Env(Ident, Vec<Expr>),
+
     // ident(arbitrary_expr,*)
     MethodCall(Ident, Vec<Expr>),
 }
 
-impl Parse for ClapAttr {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
+impl ClapAttr {
+    /// Parse a single attribute, `None` if it is invalid and the error has
+    /// already been reported.
+    fn parse(input: ParseStream) -> syn::Result<Option<Self>> {
         use self::ClapAttr::*;
 
-        let name: Ident = input.parse()?;
+        // `crate` is a keyword, so it must be parsed with `parse_any`
+        let name = input.call(Ident::parse_any)?;
         let name_str = name.to_string();
 
         if input.peek(Token![=]) {
@@ -73,7 +130,7 @@ impl Parse for ClapAttr {
 
                 let check_empty_lit = |s| {
                     if lit_str.is_empty() {
-                        abort!(
+                        emit_error!(
                             lit.span(),
                             "`#[clap({} = \"\")` is deprecated, \
                              now it's default behavior",
@@ -83,21 +140,27 @@ impl Parse for ClapAttr {
                 };
 
                 match &*name_str.to_string() {
-                    "rename_all" => Ok(RenameAll(name, lit)),
+                    "rename_all" => Ok(Some(RenameAll(name, lit))),
+
+                    "prefix" => Ok(Some(Prefix(name, lit))),
+
+                    "env_prefix" => Ok(Some(EnvPrefix(name, lit))),
+
+                    "crate" => Ok(Some(Crate(name, lit))),
 
                     "version" => {
                         check_empty_lit("version");
-                        Ok(Version(name, lit))
+                        Ok(Some(Version(name, lit)))
                     }
 
                     "author" => {
                         check_empty_lit("author");
-                        Ok(Author(name, Some(lit)))
+                        Ok(Some(Author(name, Some(lit))))
                     }
 
                     "about" => {
                         check_empty_lit("about");
-                        Ok(About(name, Some(lit)))
+                        Ok(Some(About(name, Some(lit))))
                     }
 
                     "skip" => {
@@ -106,25 +169,43 @@ impl Parse for ClapAttr {
                             lit: Lit::Str(lit),
                         };
                         let expr = Expr::Lit(expr);
-                        Ok(Skip(name, Some(expr)))
+                        Ok(Some(Skip(name, Some(expr))))
                     }
 
-                    _ => Ok(NameLitStr(name, lit)),
+                    "default_value_t" => {
+                        let expr = ExprLit {
+                            attrs: vec![],
+                            lit: Lit::Str(lit),
+                        };
+                        let expr = Expr::Lit(expr);
+                        Ok(Some(DefaultValueT(name, Some(expr))))
+                    }
+
+                    _ => Ok(Some(NameLitStr(name, lit))),
                 }
             } else {
                 match input.parse::<Expr>() {
                     Ok(expr) => {
                         if name_str == "skip" {
-                            Ok(Skip(name, Some(expr)))
+                            Ok(Some(Skip(name, Some(expr))))
+                        } else if name_str == "default_value_t" {
+                            Ok(Some(DefaultValueT(name, Some(expr))))
+                        } else if name_str == "env" {
+                            match expr {
+                                Expr::Array(ref array) => {
+                                    Ok(Some(Env(name, Vec::from_iter(array.elems.iter().cloned()))))
+                                }
+                                expr => Ok(Some(NameExpr(name, expr))),
+                            }
                         } else {
-                            Ok(NameExpr(name, expr))
+                            Ok(Some(NameExpr(name, expr)))
                         }
                     }
 
-                    Err(_) => abort! {
+                    Err(_) => Err(syn::Error::new(
                         assign_token.span(),
-                        "expected `string literal` or `expression` after `=`"
-                    },
+                        "expected `string literal` or `expression` after `=`",
+                    )),
                 }
             }
         } else if input.peek(syn::token::Paren) {
@@ -138,61 +219,87 @@ impl Parse for ClapAttr {
                         nested.parse_terminated(ParserSpec::parse)?;
 
                     if parser_specs.len() == 1 {
-                        Ok(Parse(name, parser_specs[0].clone()))
+                        Ok(Some(Parse(name, parser_specs[0].clone())))
                     } else {
-                        abort!(name.span(), "parse must have exactly one argument")
+                        emit_error!(name.span(), "parse must have exactly one argument");
+                        Ok(None)
                     }
                 }
 
-                "raw" => match nested.parse::<LitBool>() {
-                    Ok(bool_token) => {
-                        let expr = ExprLit {
-                            attrs: vec![],
-                            lit: Lit::Bool(bool_token),
-                        };
-                        let expr = Expr::Lit(expr);
-                        Ok(MethodCall(name, vec![expr]))
-                    }
+                "raw" => {
+                    let tokens: TokenStream = nested.parse()?;
+                    match parse2::<LitBool>(tokens.clone()) {
+                        Ok(bool_token) => {
+                            let expr = ExprLit {
+                                attrs: vec![],
+                                lit: Lit::Bool(bool_token),
+                            };
+                            let expr = Expr::Lit(expr);
+                            Ok(Some(MethodCall(name, vec![expr])))
+                        }
 
-                    Err(_) => {
-                        abort!(name.span(),
-                            "`#[clap(raw(...))` attributes are removed, \
-                            they are replaced with raw methods";
-                            help = "if you meant to call `clap::Arg::raw()` method \
-                                you should use bool literal, like `raw(true)` or `raw(false)`";
-                            note = raw_method_suggestion(nested);
-                        );
+                        Err(_) => {
+                            emit_error!(name.span(),
+                                "`#[clap(raw(...))` attributes are removed, \
+                                they are replaced with raw methods";
+                                help = "if you meant to call `clap::Arg::raw()` method \
+                                    you should use bool literal, like `raw(true)` or `raw(false)`";
+                                note = raw_method_suggestion(tokens);
+                            );
+                            Ok(None)
+                        }
                     }
-                },
+                }
 
                 _ => {
                     let method_args: Punctuated<_, Token![,]> =
                         nested.parse_terminated(Expr::parse)?;
-                    Ok(MethodCall(name, Vec::from_iter(method_args)))
+                    Ok(Some(MethodCall(name, Vec::from_iter(method_args))))
                 }
             }
         } else {
             // Attributes represented with a sole identifier.
             match name_str.as_ref() {
-                "long" => Ok(Long(name)),
-                "short" => Ok(Short(name)),
-                "flatten" => Ok(Flatten(name)),
-                "subcommand" => Ok(Subcommand(name)),
-                "no_version" => Ok(NoVersion(name)),
-
-                "about" => (Ok(About(name, None))),
-                "author" => (Ok(Author(name, None))),
-
-                "skip" => Ok(Skip(name, None)),
-
-                "version" => abort!(
-                    name.span(),
-                    "#[clap(version)] is invalid attribute, \
-                     clap_derive inherits version from Cargo.toml by default, \
-                     no attribute needed"
-                ),
+                "long" => Ok(Some(Long(name))),
+                "short" => Ok(Some(Short(name))),
+                "flatten" => Ok(Some(Flatten(name))),
+                "repeated" => Ok(Some(Repeated(name))),
+                "negatable" => Ok(Some(Negatable(name))),
+                "no_env" => Ok(Some(NoEnv(name))),
+                "verbatim_doc_comment" => Ok(Some(VerbatimDocComment(name))),
+                "env_only" => Ok(Some(EnvOnly(name))),
+                "subcommand" => Ok(Some(Subcommand(name))),
+                "external_subcommand" => Ok(Some(ExternalSubcommand(name))),
+                "no_version" => Ok(Some(NoVersion(name))),
+                "derive_display_order" => Ok(Some(DeriveDisplayOrder(name))),
+                "help_heading" => Ok(Some(HelpHeading(name))),
+
+                "about" => Ok(Some(About(name, None))),
+                "author" => Ok(Some(Author(name, None))),
+
+                "skip" => Ok(Some(Skip(name, None))),
+                "default_value_t" => Ok(Some(DefaultValueT(name, None))),
+
+                "version" => {
+                    emit_error!(
+                        name.span(),
+                        "#[clap(version)] is invalid attribute, \
+                         clap_derive inherits version from Cargo.toml by default, \
+                         no attribute needed"
+                    );
+                    Ok(None)
+                }
 
-                _ => abort!(name.span(), "unexpected attribute: {}", name_str),
+                _ => {
+                    match closest(&name_str, FLAG_ATTRS.iter().cloned()) {
+                        Some(candidate) => emit_error!(
+                            name.span(), "unexpected attribute: {}", name_str;
+                            help = "did you mean `{}`?", candidate
+                        ),
+                        None => emit_error!(name.span(), "unexpected attribute: {}", name_str),
+                    }
+                    Ok(None)
+                }
             }
         }
     }
@@ -223,14 +330,21 @@ impl Parse for ParserSpec {
     }
 }
 
-fn raw_method_suggestion(ts: ParseBuffer) -> String {
-    let do_parse = move || -> Result<(Ident, TokenStream), syn::Error> {
+fn skip_until_comma(input: ParseStream) -> syn::Result<()> {
+    while !input.is_empty() && !input.peek(Token![,]) {
+        input.parse::<TokenTree>()?;
+    }
+    Ok(())
+}
+
+fn raw_method_suggestion(ts: TokenStream) -> String {
+    let do_parse = |ts: ParseStream| -> syn::Result<(Ident, TokenStream)> {
         let name = ts.parse()?;
         let _eq: Token![=] = ts.parse()?;
         let val: LitStr = ts.parse()?;
         Ok((name, syn::parse_str(&val.value())?))
     };
-    if let Ok((name, val)) = do_parse() {
+    if let Ok((name, val)) = do_parse.parse2(ts) {
         let val = val.to_string().replace(" ", "").replace(",", ", ");
         format!(
             "if you need to call `clap::Arg/App::{}` method you \
@@ -245,24 +359,34 @@ fn raw_method_suggestion(ts: ParseBuffer) -> String {
     }
 }
 
-pub fn parse_clap_attributes(all_attrs: &[Attribute]) -> Vec<ClapAttr> {
-    all_attrs
+/// Parse the `#[clap(...)]` attributes, the invalid ones being reported and
+/// skipped, along with whether there was any.
+pub fn parse_clap_attributes(all_attrs: &[Attribute]) -> (Vec<ClapAttr>, bool) {
+    let mut invalid = false;
+    let attrs = all_attrs
         .iter()
         .filter(|attr| attr.path.is_ident("clap"))
         .flat_map(|attr| {
-            let attrs: ClapAttributes = parse2(attr.tokens.clone())
-                .map_err(|e| match &*e.to_string() {
-                    // this error message is misleading and points to Span::call_site()
-                    // so we patch it with something meaningful
-                    "unexpected end of input, expected parentheses" => {
-                        let span = attr.path.span();
-                        let patch_msg = "expected parentheses after `clap`";
-                        syn::Error::new(span, patch_msg)
+            match parse2::<ClapAttributes>(attr.tokens.clone()) {
+                Ok(attrs) => {
+                    invalid |= attrs.invalid;
+                    attrs.attrs
+                }
+                Err(e) => {
+                    match &*e.to_string() {
+                        // this error message is misleading and points to Span::call_site()
+                        // so we patch it with something meaningful
+                        "unexpected end of input, expected parentheses" => {
+                            let span = attr.path.span();
+                            emit_error!(span, "expected parentheses after `clap`");
+                        }
+                        _ => emit_error!(e),
                     }
-                    _ => e,
-                })
-                .unwrap_or_abort();
-            attrs.attrs
+                    invalid = true;
+                    vec![]
+                }
+            }
         })
-        .collect()
+        .collect();
+    (attrs, invalid)
 }
@@ -29,12 +29,13 @@ use proc_macro_error::proc_macro_error;
 
 mod derives;
 
-// /// It is required to have this seperate and specificly defined.
-// #[proc_macro_derive(ArgEnum, attributes(case_sensitive))]
-// pub fn arg_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-//     let input: syn::DeriveInput = syn::parse(input).unwrap();
-//     derives::derive_arg_enum(&input).into()
-// }
+/// Generates the `ArgEnum` impl.
+#[proc_macro_derive(ArgEnum, attributes(clap))]
+#[proc_macro_error]
+pub fn arg_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse(input).unwrap();
+    derives::derive_arg_enum(&input).into()
+}
 
 /// Generates the `Clap` impl.
 #[proc_macro_derive(Clap, attributes(clap))]
@@ -53,7 +54,7 @@ pub fn into_app(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 }
 
 /// Generates the `FromArgMatches` impl.
-#[proc_macro_derive(FromArgMatches)]
+#[proc_macro_derive(FromArgMatches, attributes(clap))]
 #[proc_macro_error]
 pub fn from_argmatches(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input: syn::DeriveInput = syn::parse(input).unwrap();
@@ -1,54 +1,99 @@
-// // Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
-// // Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
-// // Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
-// //
-// // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
-// // http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
-// // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
-// // option. This file may not be copied, modified, or distributed
-// // except according to those terms.
-// #[macro_use]
-// extern crate clap;
-// #[macro_use]
-// extern crate clap_derive;
-
-// use clap::{App, Arg};
-
-// #[derive(ArgEnum, Debug, PartialEq)]
-// enum ArgChoice {
-//     Foo,
-//     Bar,
-//     Baz,
-// }
-
-// #[test]
-// fn when_lowercase() {
-//     let matches = App::new(env!("CARGO_PKG_NAME"))
-//         .arg(
-//             Arg::with_name("arg")
-//                 .required(true)
-//                 .takes_value(true)
-//                 .possible_values(&ArgChoice::variants()),
-//         )
-//         .try_get_matches_from(vec!["", "foo"])
-//         .unwrap();
-//     let t = value_t!(matches.value_of("arg"), ArgChoice);
-//     assert!(t.is_ok());
-//     assert_eq!(t.unwrap(), ArgChoice::Foo);
-// }
-
-// #[test]
-// fn when_capitalized() {
-//     let matches = App::new(env!("CARGO_PKG_NAME"))
-//         .arg(
-//             Arg::with_name("arg")
-//                 .required(true)
-//                 .takes_value(true)
-//                 .possible_values(&ArgChoice::variants()),
-//         )
-//         .try_get_matches_from(vec!["", "Foo"])
-//         .unwrap();
-//     let t = value_t!(matches.value_of("arg"), ArgChoice);
-//     assert!(t.is_ok());
-//     assert_eq!(t.unwrap(), ArgChoice::Foo);
-// }
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, Arg, ArgEnum};
+
+#[derive(ArgEnum, Debug, PartialEq)]
+enum ArgChoice {
+    Foo,
+    Bar,
+    Baz,
+}
+
+#[test]
+fn variants() {
+    assert_eq!(ArgChoice::VARIANTS, &["Foo", "Bar", "Baz"]);
+}
+
+#[test]
+fn when_exact() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS),
+        )
+        .try_get_matches_from(vec!["", "Foo"])
+        .unwrap();
+    let t = matches.value_of("arg").unwrap().parse::<ArgChoice>();
+    assert_eq!(t, Ok(ArgChoice::Foo));
+}
+
+#[test]
+fn when_lowercase() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS),
+        )
+        .try_get_matches_from(vec!["", "foo"]); // We expect this to fail.
+    assert!(matches.is_err());
+    assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::InvalidValue);
+    assert!("foo".parse::<ArgChoice>().is_err());
+}
+
+#[test]
+fn from_str_error() {
+    assert_eq!(
+        "qux".parse::<ArgChoice>(),
+        Err("valid values: Foo, Bar, Baz".to_string())
+    );
+}
+
+#[test]
+fn display() {
+    assert_eq!(ArgChoice::Bar.to_string(), "Bar");
+}
+
+#[test]
+fn rename_all() {
+    #[derive(ArgEnum, Debug, PartialEq)]
+    #[clap(rename_all = "kebab")]
+    enum LogLevel {
+        Warn,
+        DebugAll,
+    }
+
+    assert_eq!(LogLevel::VARIANTS, &["warn", "debug-all"]);
+    assert_eq!("debug-all".parse::<LogLevel>(), Ok(LogLevel::DebugAll));
+    assert_eq!(LogLevel::DebugAll.to_string(), "debug-all");
+}
+
+#[test]
+fn name_and_alias() {
+    #[derive(ArgEnum, Debug, PartialEq)]
+    #[clap(rename_all = "snake")]
+    enum Format {
+        #[clap(name = "yml", alias = "yaml")]
+        Yaml,
+        #[clap(alias = "js", alias = "ecmascript")]
+        JavaScript,
+    }
+
+    assert_eq!(Format::VARIANTS, &["yml", "java_script"]);
+    assert_eq!("yml".parse::<Format>(), Ok(Format::Yaml));
+    assert_eq!("yaml".parse::<Format>(), Ok(Format::Yaml));
+    assert_eq!("js".parse::<Format>(), Ok(Format::JavaScript));
+    assert_eq!("ecmascript".parse::<Format>(), Ok(Format::JavaScript));
+    assert_eq!(Format::Yaml.to_string(), "yml");
+}
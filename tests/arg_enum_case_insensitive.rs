@@ -0,0 +1,57 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, Arg, ArgEnum};
+
+#[derive(ArgEnum, Debug, PartialEq)]
+#[clap(case_insensitive = true)]
+enum ArgChoice {
+    Foo,
+    Bar,
+    #[clap(alias = "qux")]
+    Baz,
+}
+
+#[test]
+fn when_lowercase() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS)
+                .case_insensitive(ArgChoice::CASE_INSENSITIVE),
+        )
+        .try_get_matches_from(vec!["", "foo"])
+        .unwrap();
+    let t = matches.value_of("arg").unwrap().parse::<ArgChoice>();
+    assert_eq!(t, Ok(ArgChoice::Foo));
+}
+
+#[test]
+fn when_capitalized() {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .arg(
+            Arg::with_name("arg")
+                .required(true)
+                .takes_value(true)
+                .possible_values(ArgChoice::VARIANTS)
+                .case_insensitive(ArgChoice::CASE_INSENSITIVE),
+        )
+        .try_get_matches_from(vec!["", "BAR"])
+        .unwrap();
+    let t = matches.value_of("arg").unwrap().parse::<ArgChoice>();
+    assert_eq!(t, Ok(ArgChoice::Bar));
+}
+
+#[test]
+fn alias() {
+    assert_eq!("QUX".parse::<ArgChoice>(), Ok(ArgChoice::Baz));
+}
@@ -0,0 +1,133 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use clap::{ArgEnum, Clap};
+use utils::*;
+
+#[derive(ArgEnum, Debug, PartialEq)]
+#[clap(rename_all = "kebab")]
+enum Level {
+    Warn,
+    Info,
+    DebugAll,
+}
+
+#[derive(ArgEnum, Debug, PartialEq)]
+#[clap(case_insensitive = true)]
+enum Color {
+    Always,
+    Never,
+}
+
+#[test]
+fn possible_values_in_help() {
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: Level,
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("[possible values: warn, info, debug-all]"));
+}
+
+#[test]
+fn invalid_value_is_rejected_by_clap() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        level: Level,
+    }
+
+    assert_eq!(
+        Opt {
+            level: Level::DebugAll
+        },
+        Opt::parse_from(&["test", "debug-all"])
+    );
+
+    let err = Opt::try_parse_from(&["test", "Warn"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::InvalidValue);
+}
+
+#[test]
+fn case_insensitive() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        color: Color,
+    }
+
+    assert_eq!(
+        Opt {
+            color: Color::Never
+        },
+        Opt::parse_from(&["test", "--color", "NEVER"])
+    );
+}
+
+#[test]
+fn option_and_vec() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: Option<Level>,
+        #[clap(long)]
+        colors: Vec<Color>,
+    }
+
+    assert_eq!(
+        Opt {
+            level: None,
+            colors: vec![],
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            level: Some(Level::Info),
+            colors: vec![Color::Always, Color::Never],
+        },
+        Opt::parse_from(&["test", "--level", "info", "--colors", "always", "Never"])
+    );
+
+    let err = Opt::try_parse_from(&["test", "--level", "error"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::InvalidValue);
+}
+
+#[test]
+fn explicit_possible_values_win() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, possible_values = &["warn", "info"])]
+        level: Level,
+    }
+
+    assert!(Opt::try_parse_from(&["test", "--level", "info"]).is_ok());
+    let err = Opt::try_parse_from(&["test", "--level", "debug-all"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::InvalidValue);
+}
+
+#[test]
+fn other_types_are_untouched() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        name: String,
+    }
+
+    assert_eq!(
+        Opt {
+            name: "whatever".into()
+        },
+        Opt::parse_from(&["test", "--name", "whatever"])
+    );
+}
@@ -0,0 +1,165 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, Arg, ArgMatches, Args, Clap, Error, FromArgMatches, Subcommand};
+
+#[derive(Debug, PartialEq)]
+struct Verbosity(u64);
+
+impl Args for Verbosity {
+    fn augment_args<'b>(app: App<'b>) -> App<'b> {
+        app.arg(
+            Arg::with_name("verbose")
+                .short('v')
+                .multiple_occurrences(true),
+        )
+    }
+}
+
+impl FromArgMatches for Verbosity {
+    fn from_argmatches(matches: &ArgMatches) -> Self {
+        Verbosity(matches.occurrences_of("verbose"))
+    }
+
+    fn try_from_argmatches(matches: &ArgMatches) -> Result<Self, Error> {
+        Ok(Self::from_argmatches(matches))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Manual {
+    Ping,
+    Echo(String),
+}
+
+impl Subcommand for Manual {
+    fn augment_subcommands<'b>(app: App<'b>) -> App<'b> {
+        app.subcommand(App::new("ping"))
+            .subcommand(App::new("echo").arg(Arg::with_name("text").required(true)))
+    }
+
+    fn from_subcommand(subcommand: (&str, Option<&ArgMatches>)) -> Option<Self> {
+        Self::try_from_subcommand(subcommand).unwrap()
+    }
+
+    fn try_from_subcommand(
+        subcommand: (&str, Option<&ArgMatches>),
+    ) -> Result<Option<Self>, Error> {
+        Ok(match subcommand {
+            ("ping", _) => Some(Manual::Ping),
+            ("echo", Some(matches)) => {
+                Some(Manual::Echo(matches.value_of("text").unwrap().to_string()))
+            }
+            _ => None,
+        })
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        name == "ping" || name == "echo"
+    }
+}
+
+#[test]
+fn flatten_manual_args() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(flatten)]
+        verbosity: Verbosity,
+        name: String,
+    }
+
+    assert_eq!(
+        Opt {
+            verbosity: Verbosity(2),
+            name: "foo".to_string()
+        },
+        Opt::parse_from(&["test", "-vv", "foo"])
+    );
+}
+
+#[test]
+fn manual_subcommand() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(subcommand)]
+        cmd: Manual,
+    }
+
+    assert_eq!(
+        Opt { cmd: Manual::Ping },
+        Opt::parse_from(&["test", "ping"])
+    );
+    assert_eq!(
+        Opt {
+            cmd: Manual::Echo("hi".to_string())
+        },
+        Opt::parse_from(&["test", "echo", "hi"])
+    );
+}
+
+#[test]
+fn generic_wrapper() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt<A, S> {
+        #[clap(flatten)]
+        args: A,
+        #[clap(subcommand)]
+        cmd: Option<S>,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    enum Derived {
+        Start,
+    }
+
+    assert_eq!(
+        Opt {
+            args: Verbosity(1),
+            cmd: Some(Manual::Ping)
+        },
+        Opt::<Verbosity, Manual>::parse_from(&["test", "-v", "ping"])
+    );
+    assert_eq!(
+        Opt {
+            args: Verbosity(0),
+            cmd: Some(Derived::Start)
+        },
+        Opt::<Verbosity, Derived>::parse_from(&["test", "start"])
+    );
+    assert_eq!(
+        Opt {
+            args: Verbosity(0),
+            cmd: None
+        },
+        Opt::<Verbosity, Derived>::parse_from(&["test"])
+    );
+}
+
+#[test]
+fn derived_has_subcommand() {
+    #[derive(Clap, Debug, PartialEq)]
+    enum Opt {
+        Add,
+        #[clap(name = "rm")]
+        Remove,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    enum External {
+        Add,
+        #[clap(external_subcommand)]
+        Other(Vec<String>),
+    }
+
+    assert!(Opt::has_subcommand("add"));
+    assert!(Opt::has_subcommand("rm"));
+    assert!(!Opt::has_subcommand("remove"));
+    assert!(External::has_subcommand("anything"));
+}
@@ -0,0 +1,21 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{ArgEnum, Clap};
+
+#[derive(Clap, ArgEnum, Debug, PartialEq)]
+enum Opt {
+    Start,
+    Stop,
+}
+
+fn main() {
+    assert_eq!(Opt::parse_from(&["test", "stop"]), Opt::Stop);
+    assert_eq!("Start".parse::<Opt>(), Ok(Opt::Start));
+    assert_eq!(Opt::VARIANTS, &["Start", "Stop"]);
+}
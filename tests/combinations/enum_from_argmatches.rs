@@ -0,0 +1,33 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, Arg, FromArgMatches, IntoApp};
+
+#[derive(FromArgMatches, Debug, PartialEq)]
+enum Opt {
+    Add { name: String },
+    Remove,
+}
+
+impl IntoApp for Opt {
+    fn into_app<'b>() -> App<'b> {
+        App::new("test")
+            .subcommand(App::new("add").arg(Arg::with_name("name").required(true)))
+            .subcommand(App::new("remove"))
+    }
+}
+
+fn main() {
+    let matches = Opt::into_app().get_matches_from(&["test", "add", "foo"]);
+    assert_eq!(
+        Opt::from_argmatches(&matches),
+        Opt::Add {
+            name: "foo".to_string()
+        }
+    );
+}
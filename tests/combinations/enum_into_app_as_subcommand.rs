@@ -0,0 +1,65 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{ArgMatches, Clap, Error, ErrorKind, FromArgMatches, IntoApp};
+
+#[derive(Clap, Debug, PartialEq)]
+struct Opt {
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[derive(IntoApp, Debug, PartialEq)]
+enum Command {
+    Add { name: String },
+    Remove,
+}
+
+impl FromArgMatches for Command {
+    fn from_argmatches(matches: &ArgMatches) -> Self {
+        Self::try_from_argmatches(matches).unwrap()
+    }
+
+    fn try_from_argmatches(matches: &ArgMatches) -> Result<Self, Error> {
+        match matches.subcommand() {
+            ("add", Some(sub)) => Ok(Command::Add {
+                name: sub.value_of("name").unwrap().to_string(),
+            }),
+            ("remove", _) => Ok(Command::Remove),
+            _ => Err(Error::with_description(
+                "unknown subcommand",
+                ErrorKind::UnrecognizedSubcommand,
+            )),
+        }
+    }
+}
+
+fn main() {
+    assert_eq!(
+        Opt::parse_from(&["test", "add", "foo"]),
+        Opt {
+            cmd: Command::Add {
+                name: "foo".to_string()
+            }
+        }
+    );
+    assert_eq!(
+        Opt::parse_from(&["test", "remove"]),
+        Opt {
+            cmd: Command::Remove
+        }
+    );
+
+    let matches = Command::into_app().get_matches_from(&["test", "add", "bar"]);
+    assert_eq!(
+        Command::from_argmatches(&matches),
+        Command::Add {
+            name: "bar".to_string()
+        }
+    );
+}
@@ -0,0 +1,42 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{FromArgMatches, IntoApp};
+
+#[derive(IntoApp, FromArgMatches, Debug, PartialEq)]
+struct Opt {
+    #[clap(flatten)]
+    common: Common,
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[derive(IntoApp, FromArgMatches, Debug, PartialEq)]
+struct Common {
+    #[clap(short)]
+    verbose: bool,
+}
+
+#[derive(IntoApp, FromArgMatches, Debug, PartialEq)]
+enum Command {
+    Add { name: String },
+    Remove,
+}
+
+fn main() {
+    let matches = Opt::into_app().get_matches_from(&["test", "-v", "add", "foo"]);
+    assert_eq!(
+        Opt::from_argmatches(&matches),
+        Opt {
+            common: Common { verbose: true },
+            cmd: Command::Add {
+                name: "foo".to_string()
+            }
+        }
+    );
+}
@@ -0,0 +1,26 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug, PartialEq)]
+struct Opt {
+    #[clap(short)]
+    verbose: bool,
+    name: String,
+}
+
+fn main() {
+    assert_eq!(
+        Opt::parse_from(&["test", "-v", "foo"]),
+        Opt {
+            verbose: true,
+            name: "foo".to_string()
+        }
+    );
+}
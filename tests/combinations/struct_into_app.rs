@@ -0,0 +1,22 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::IntoApp;
+
+#[derive(IntoApp)]
+struct Opt {
+    #[clap(short)]
+    verbose: bool,
+    name: String,
+}
+
+fn main() {
+    let matches = Opt::into_app().get_matches_from(&["test", "-v", "foo"]);
+    assert!(matches.is_present("verbose"));
+    assert_eq!(matches.value_of("name"), Some("foo"));
+}
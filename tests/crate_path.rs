@@ -0,0 +1,80 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod cli_kit {
+    pub mod clap {
+        pub use ::clap::*;
+    }
+}
+
+use cli_kit::clap::{ArgEnum, Clap};
+
+#[derive(ArgEnum, PartialEq, Debug)]
+#[clap(crate = "crate::cli_kit::clap")]
+enum Color {
+    Red,
+    Green,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(crate = "crate::cli_kit::clap")]
+struct Common {
+    #[clap(short, long)]
+    verbose: bool,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(crate = "crate::cli_kit::clap")]
+enum Command {
+    Paint { color: Color },
+    Erase,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(crate = "crate::cli_kit::clap")]
+struct Opt {
+    #[clap(long, default_value = "1")]
+    count: u32,
+    #[clap(flatten)]
+    common: Common,
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[test]
+fn custom_crate_path() {
+    assert_eq!(
+        Opt {
+            count: 3,
+            common: Common { verbose: true },
+            cmd: Command::Paint { color: Color::Green },
+        },
+        Opt::parse_from(&["test", "--count", "3", "-v", "paint", "Green"])
+    );
+    assert_eq!(
+        Opt {
+            count: 1,
+            common: Common { verbose: false },
+            cmd: Command::Erase,
+        },
+        Opt::parse_from(&["test", "erase"])
+    );
+}
+
+#[test]
+fn custom_crate_path_into_app() {
+    use cli_kit::clap::IntoApp;
+
+    let mut help = Vec::new();
+    Opt::into_app().write_help(&mut help).unwrap();
+    let help = String::from_utf8(help).unwrap();
+    assert!(help.contains("--count"));
+    assert!(help.contains("paint"));
+}
@@ -0,0 +1,119 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+const PORT: u16 = 8080;
+
+#[test]
+fn default_value_t() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, default_value_t = PORT)]
+        port: u16,
+    }
+
+    assert_eq!(Opt { port: 8080 }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { port: 80 }, Opt::parse_from(&["test", "--port", "80"]));
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("[default: 8080]"));
+}
+
+#[test]
+fn auto_default_value_t() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, default_value_t)]
+        retries: u32,
+    }
+
+    assert_eq!(Opt { retries: 0 }, Opt::parse_from(&["test"]));
+    assert_eq!(
+        Opt { retries: 3 },
+        Opt::parse_from(&["test", "--retries", "3"])
+    );
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("[default: 0]"));
+}
+
+#[test]
+fn default_value_t_is_not_parsed() {
+    #[derive(PartialEq, Debug)]
+    struct Level(u8);
+
+    impl std::fmt::Display for Level {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "level {}", self.0)
+        }
+    }
+
+    impl std::str::FromStr for Level {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Level)
+        }
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, default_value_t = Level(2))]
+        level: Level,
+    }
+
+    assert_eq!(Opt { level: Level(2) }, Opt::parse_from(&["test"]));
+    assert_eq!(
+        Opt { level: Level(5) },
+        Opt::parse_from(&["test", "--level", "5"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--level", "high"]).is_err());
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("[default: level 2]"));
+}
+
+#[test]
+fn default_value_t_with_environment() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "DEFAULT_VALUE_T_")]
+    struct Opt {
+        #[clap(long, default_value_t = PORT)]
+        port: u16,
+        #[clap(long, default_value_t, env = ["DEFAULT_VALUE_T_JOBS"])]
+        jobs: u32,
+    }
+
+    assert_eq!(
+        Opt {
+            port: 8080,
+            jobs: 0
+        },
+        Opt::parse_from(&["test"])
+    );
+
+    std::env::set_var("DEFAULT_VALUE_T_PORT", "9090");
+    std::env::set_var("DEFAULT_VALUE_T_JOBS", "4");
+    assert_eq!(
+        Opt {
+            port: 9090,
+            jobs: 4
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt { port: 80, jobs: 4 },
+        Opt::parse_from(&["test", "--port", "80"])
+    );
+}
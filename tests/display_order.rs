@@ -0,0 +1,150 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+fn assert_displayed_in_order(help: &str, names: &[&str]) {
+    let positions: Vec<usize> = names
+        .iter()
+        .map(|name| {
+            help.find(name)
+                .unwrap_or_else(|| panic!("`{}` not found in:\n{}", name, help))
+        })
+        .collect();
+    assert!(
+        positions.windows(2).all(|w| w[0] < w[1]),
+        "{:?} not in order in:\n{}",
+        names,
+        help
+    );
+}
+
+#[test]
+fn arguments_in_order_of_declaration() {
+    #[derive(Clap, Debug)]
+    #[clap(derive_display_order)]
+    struct Opt {
+        #[clap(long)]
+        zebra: bool,
+        #[clap(long)]
+        yak: bool,
+        #[clap(long)]
+        antelope: bool,
+    }
+
+    assert_displayed_in_order(&get_help::<Opt>(), &["--zebra", "--yak", "--antelope"]);
+}
+
+#[test]
+fn flattened_arguments_in_order_of_declaration() {
+    #[derive(Clap, Debug)]
+    struct Inner {
+        #[clap(long)]
+        yak: bool,
+        #[clap(long)]
+        buffalo: bool,
+    }
+
+    #[derive(Clap, Debug)]
+    #[clap(derive_display_order)]
+    struct Opt {
+        #[clap(long)]
+        zebra: bool,
+        #[clap(flatten)]
+        inner: Inner,
+        #[clap(long)]
+        antelope: bool,
+    }
+
+    assert_displayed_in_order(
+        &get_help::<Opt>(),
+        &["--zebra", "--yak", "--buffalo", "--antelope"],
+    );
+}
+
+#[test]
+fn explicit_display_order_wins() {
+    #[derive(Clap, Debug)]
+    #[clap(derive_display_order)]
+    struct Opt {
+        #[clap(long, display_order = 100)]
+        antelope: bool,
+        #[clap(long)]
+        zebra: bool,
+        #[clap(long)]
+        yak: bool,
+    }
+
+    assert_displayed_in_order(&get_help::<Opt>(), &["--zebra", "--yak", "--antelope"]);
+}
+
+#[test]
+fn subcommands_in_order_of_declaration() {
+    #[derive(Clap, Debug)]
+    #[clap(derive_display_order)]
+    enum Opt {
+        Zebra,
+        Yak {
+            #[clap(long)]
+            zebu: bool,
+            #[clap(long)]
+            buffalo: bool,
+        },
+        Antelope,
+    }
+
+    assert_displayed_in_order(&get_help::<Opt>(), &["zebra", "yak", "antelope"]);
+    assert_displayed_in_order(
+        &get_subcommand_long_help::<Opt>("yak"),
+        &["--zebu", "--buffalo"],
+    );
+}
+
+#[test]
+fn subcommands_of_struct_in_order_of_declaration() {
+    #[derive(Clap, Debug)]
+    enum Command {
+        Zebra,
+        Yak {
+            #[clap(long)]
+            zebu: bool,
+            #[clap(long)]
+            buffalo: bool,
+        },
+        #[clap(derive_display_order)]
+        Antelope {
+            #[clap(long)]
+            gnu: bool,
+            #[clap(long)]
+            bison: bool,
+        },
+    }
+
+    #[derive(Clap, Debug)]
+    #[clap(derive_display_order)]
+    struct Opt {
+        #[clap(subcommand)]
+        command: Command,
+    }
+
+    assert_displayed_in_order(&get_help::<Opt>(), &["zebra", "yak", "antelope"]);
+    // the subcommands which are not marked keep the alphabetical order
+    assert_displayed_in_order(
+        &get_subcommand_long_help::<Opt>("yak"),
+        &["--buffalo", "--zebu"],
+    );
+    assert_displayed_in_order(
+        &get_subcommand_long_help::<Opt>("antelope"),
+        &["--gnu", "--bison"],
+    );
+}
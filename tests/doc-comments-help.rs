@@ -114,3 +114,170 @@ fn top_long_doc_comment_both_help_long_help() {
     assert!(long_help.contains("DO NOT PASS A BAR UNDER ANY CIRCUMSTANCES"));
     assert!(long_help.contains("Or something else"));
 }
+
+#[test]
+fn first_paragraph_is_short_help() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "lorem-ipsum", no_version)]
+    struct LoremIpsum {
+        /// Fooify a bar
+        /// and a baz.
+        ///
+        /// Or something else
+        #[clap(long)]
+        foo: bool,
+    }
+
+    let short_help = get_help::<LoremIpsum>();
+    let long_help = get_long_help::<LoremIpsum>();
+
+    assert!(short_help.contains("Fooify a bar and a baz"));
+    assert!(!short_help.contains("and a baz."));
+    assert!(!short_help.contains("Or something else"));
+    assert!(long_help.contains("Fooify a bar and a baz.\n"));
+    assert!(long_help.contains("Or something else"));
+}
+
+#[test]
+fn markdown_inline_markup_is_stripped() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "lorem-ipsum", no_version)]
+    struct LoremIpsum {
+        /// Use `--fast` with **care**, see [docs](https://docs.rs) and [`LoremIpsum`]
+        #[clap(long)]
+        fast: bool,
+        /// Keep snake_case, 2 * 3 and [default: 3]
+        #[clap(long)]
+        keep: bool,
+    }
+
+    let help = get_help::<LoremIpsum>();
+    assert!(help.contains(
+        "Use --fast with care, see docs <https://docs.rs> and LoremIpsum"
+    ));
+    assert!(help.contains("Keep snake_case, 2 * 3 and [default: 3]"));
+}
+
+#[test]
+fn markdown_lists_and_code_blocks_are_kept() {
+    /// Convert files
+    ///
+    /// Supported formats:
+    ///
+    /// * `json`
+    /// * `yaml`, with
+    ///   comments
+    ///
+    /// ```
+    /// # fn main() {}
+    /// $ convert a.json
+    ///     b.yaml
+    /// ```
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "lorem-ipsum", no_version)]
+    struct LoremIpsum {}
+
+    let help = get_long_help::<LoremIpsum>();
+    assert!(help.starts_with(
+        "lorem-ipsum \nConvert files\n\nSupported formats:\n\n- json\n- yaml, with comments\n\n\
+         $ convert a.json\n    b.yaml\n\nUSAGE:"
+    ));
+}
+
+#[test]
+fn code_blocks_indented_with_multibyte_whitespace() {
+    /// Convert files
+    ///
+    ///   ```text
+    ///  　$ convert a.json
+    ///   ```
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "lorem-ipsum", no_version)]
+    struct LoremIpsum {}
+
+    let help = get_long_help::<LoremIpsum>();
+    assert!(help.starts_with("lorem-ipsum \nConvert files\n\n$ convert a.json\n\nUSAGE:"));
+}
+
+#[test]
+fn verbatim_doc_comment() {
+    /// DANCE!
+    ///
+    ///                    ()
+    ///                    |
+    ///               (   ()   )
+    ///                ) ________    //  )
+    ///             ()  |\       \  //
+    ///     ( \\__ \ ______\//
+    ///        \__) |       |
+    ///          |  |       |
+    ///           \ |___    |
+    ///           \__ \  /\ |
+    ///            ||_|_\/
+    #[derive(Clap, Debug)]
+    #[clap(name = "lorem-ipsum", no_version, verbatim_doc_comment)]
+    struct SeeFigure1 {
+        /// Formats:
+        ///   json   `{"a": 1}`
+        ///   toml   `a = 1`
+        #[clap(long, verbatim_doc_comment)]
+        format: Option<String>,
+    }
+
+    let help = get_long_help::<SeeFigure1>();
+    let sample = r#"
+                   ()
+                   |
+              (   ()   )
+               ) ________    //  )
+            ()  |\       \  //
+    ( \\__ \ ______\//
+       \__) |       |
+         |  |       |
+          \ |___    |
+          \__ \  /\ |
+           ||_|_\/"#;
+
+    assert!(help.starts_with("lorem-ipsum \nDANCE!\n"));
+    assert!(help.contains(sample));
+    // the help of the argument is indented by clap
+    assert!(help.contains("Formats:\n"));
+    assert!(help.contains("  json   `{\"a\": 1}`\n"));
+    assert!(help.contains("  toml   `a = 1`"));
+}
+
+#[test]
+fn rustdoc_sections_are_after_long_help() {
+    /// Convert files
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// $ convert a.json
+    /// ```
+    ///
+    /// # Exit status
+    ///
+    /// - 0 on success
+    /// - 1 on failure
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "lorem-ipsum", no_version)]
+    struct LoremIpsum {
+        /// Fooify a bar
+        ///
+        /// # Note
+        ///
+        /// Or something else
+        #[clap(long)]
+        foo: bool,
+    }
+
+    let help = get_long_help::<LoremIpsum>();
+    assert!(help.starts_with("lorem-ipsum \nConvert files\n\nUSAGE:"));
+    assert!(help.ends_with(
+        "\n\nEXAMPLES:\n    $ convert a.json\n\nEXIT STATUS:\n    - 0 on success\n    - 1 on failure"
+    ));
+    // the arguments don't have sections
+    assert!(help.contains("Note"));
+    assert!(!help.contains("NOTE:"));
+}
@@ -0,0 +1,199 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use clap::{Clap, FromArgMatches, IntoApp};
+use std::env;
+use utils::*;
+
+#[test]
+fn env_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "ENV_PREFIX_TEST_")]
+    struct Opt {
+        #[clap(long, default_value = "8080")]
+        port: u16,
+        #[clap(long)]
+        max_conn: Option<u32>,
+        #[clap(long, no_env)]
+        name: Option<String>,
+        #[clap(short)]
+        verbose: bool,
+    }
+
+    env::set_var("ENV_PREFIX_TEST_PORT", "9000");
+    env::set_var("ENV_PREFIX_TEST_MAX_CONN", "5");
+    env::set_var("ENV_PREFIX_TEST_NAME", "ignored");
+    env::set_var("ENV_PREFIX_TEST_VERBOSE", "1");
+
+    assert_eq!(
+        Opt {
+            port: 9000,
+            max_conn: Some(5),
+            name: None,
+            verbose: false,
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            port: 80,
+            max_conn: Some(5),
+            name: None,
+            verbose: false,
+        },
+        Opt::parse_from(&["test", "--port", "80"])
+    );
+    assert!(get_long_help::<Opt>().contains("ENV_PREFIX_TEST_MAX_CONN"));
+}
+
+#[test]
+fn env_prefix_through_flatten_and_subcommands() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Db {
+        #[clap(long, default_value = "localhost")]
+        host: String,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "CACHE_")]
+    struct Cache {
+        #[clap(long, default_value = "0")]
+        size: u32,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    enum Command {
+        Run {
+            #[clap(long)]
+            jobs: Option<u32>,
+        },
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "ENV_NESTED_")]
+    struct Opt {
+        #[clap(flatten, prefix = "db-")]
+        db: Db,
+        #[clap(flatten)]
+        cache: Cache,
+        #[clap(subcommand)]
+        command: Command,
+    }
+
+    env::set_var("ENV_NESTED_DB_HOST", "db.internal");
+    env::set_var("ENV_NESTED_CACHE_SIZE", "64");
+    env::set_var("ENV_NESTED_JOBS", "4");
+
+    assert_eq!(
+        Opt {
+            db: Db {
+                host: "db.internal".to_string()
+            },
+            cache: Cache { size: 64 },
+            command: Command::Run { jobs: Some(4) },
+        },
+        Opt::parse_from(&["test", "run"])
+    );
+    assert_eq!(
+        Opt {
+            db: Db {
+                host: "db.local".to_string()
+            },
+            cache: Cache { size: 64 },
+            command: Command::Run { jobs: Some(2) },
+        },
+        Opt::parse_from(&["test", "--db-host", "db.local", "run", "--jobs", "2"])
+    );
+}
+
+#[test]
+fn env_fallback_names() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, env = ["ENV_FALLBACK_NEW", "ENV_FALLBACK_LEGACY"])]
+        token: Option<String>,
+        #[clap(long, env = ["ENV_FALLBACK_HOST", "ENV_FALLBACK_OLD_HOST"])]
+        host: String,
+    }
+
+    assert!(Opt::try_parse_from(&["test"]).is_err());
+    assert_eq!(
+        Opt {
+            token: None,
+            host: "a".to_string()
+        },
+        Opt::parse_from(&["test", "--host", "a"])
+    );
+
+    // the variables are read when parsing, not when building the app
+    let app = Opt::into_app();
+    env::set_var("ENV_FALLBACK_LEGACY", "legacy");
+    env::set_var("ENV_FALLBACK_OLD_HOST", "old");
+    assert_eq!(
+        Opt {
+            token: Some("legacy".to_string()),
+            host: "old".to_string()
+        },
+        Opt::from_argmatches(&app.get_matches_from(&["test"]))
+    );
+
+    env::set_var("ENV_FALLBACK_NEW", "new");
+    assert_eq!(
+        Opt {
+            token: Some("new".to_string()),
+            host: "b".to_string()
+        },
+        Opt::parse_from(&["test", "--host", "b"])
+    );
+    assert!(get_long_help::<Opt>().contains("ENV_FALLBACK_NEW"));
+}
+
+#[test]
+fn env_only() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "ENV_ONLY_")]
+    struct Opt {
+        #[clap(env_only)]
+        secret: Option<String>,
+        #[clap(env_only, env = "ENV_ONLY_TEST_TIMEOUT", default_value_t = 30)]
+        timeout: u64,
+        #[clap(env_only)]
+        region: String,
+    }
+
+    assert!(Opt::try_parse_from(&["test"]).is_err());
+
+    env::set_var("ENV_ONLY_REGION", "eu");
+    assert_eq!(
+        Opt {
+            secret: None,
+            timeout: 30,
+            region: "eu".to_string(),
+        },
+        Opt::parse_from(&["test"])
+    );
+
+    env::set_var("ENV_ONLY_SECRET", "hunter2");
+    env::set_var("ENV_ONLY_TEST_TIMEOUT", "5");
+    assert_eq!(
+        Opt {
+            secret: Some("hunter2".to_string()),
+            timeout: 5,
+            region: "eu".to_string(),
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--secret", "x"]).is_err());
+
+    env::set_var("ENV_ONLY_TEST_TIMEOUT", "soon");
+    assert!(Opt::try_parse_from(&["test"]).is_err());
+}
@@ -143,3 +143,51 @@ fn combined_flags() {
         Opt::parse_from(&["test", "-bb", "-a", "-bb"])
     );
 }
+
+#[test]
+fn negatable_bool() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, negatable)]
+        color: bool,
+    }
+
+    assert_eq!(Opt { color: false }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { color: true }, Opt::parse_from(&["test", "--color"]));
+    assert_eq!(Opt { color: false }, Opt::parse_from(&["test", "--no-color"]));
+    assert_eq!(
+        Opt { color: false },
+        Opt::parse_from(&["test", "--color", "--no-color"])
+    );
+    assert_eq!(
+        Opt { color: true },
+        Opt::parse_from(&["test", "--no-color", "--color"])
+    );
+}
+
+#[test]
+fn negatable_option_bool() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long = "colour", negatable)]
+        color: Option<bool>,
+    }
+
+    assert_eq!(Opt { color: None }, Opt::parse_from(&["test"]));
+    assert_eq!(
+        Opt { color: Some(true) },
+        Opt::parse_from(&["test", "--colour"])
+    );
+    assert_eq!(
+        Opt { color: Some(false) },
+        Opt::parse_from(&["test", "--no-colour"])
+    );
+    assert_eq!(
+        Opt { color: Some(true) },
+        Opt::parse_from(&["test", "--colour", "--no-colour", "--colour"])
+    );
+    assert_eq!(
+        Opt { color: Some(false) },
+        Opt::parse_from(&["test", "--colour", "--no-colour"])
+    );
+}
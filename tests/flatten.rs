@@ -55,6 +55,31 @@ fn flatten_twice() {
     Opt::parse_from(&["test", "42", "43"]);
 }
 
+#[test]
+fn flatten_duplicate_with_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Common {
+        #[clap(long)]
+        level: i32,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: i32,
+        // the prefix keeps the ids and long names apart
+        #[clap(flatten, prefix = "inner-")]
+        common: Common,
+    }
+    assert_eq!(
+        Opt {
+            level: 1,
+            common: Common { level: 2 }
+        },
+        Opt::parse_from(&["test", "--level", "1", "--inner-level", "2"])
+    );
+}
+
 #[test]
 fn flatten_in_subcommand() {
     #[derive(Clap, PartialEq, Debug)]
@@ -97,3 +122,433 @@ fn flatten_in_subcommand() {
         Opt::parse_from(&["test", "add", "-i", "43"])
     );
 }
+
+#[derive(Clap, PartialEq, Debug)]
+struct DbArgs {
+    #[clap(long)]
+    host: String,
+    #[clap(long, default_value = "5432")]
+    port: u16,
+    #[clap(long, env = "FLATTEN_PREFIX_DB_USER")]
+    user: Option<String>,
+}
+
+#[test]
+fn flatten_with_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten)]
+        primary: DbArgs,
+        #[clap(flatten, prefix = "replica-")]
+        replica: DbArgs,
+    }
+
+    assert_eq!(
+        Opt {
+            primary: DbArgs {
+                host: "main".into(),
+                port: 5432,
+                user: None,
+            },
+            replica: DbArgs {
+                host: "backup".into(),
+                port: 5433,
+                user: Some("reader".into()),
+            },
+        },
+        Opt::parse_from(&[
+            "test",
+            "--host",
+            "main",
+            "--replica-host",
+            "backup",
+            "--replica-port",
+            "5433",
+            "--replica-user",
+            "reader",
+        ])
+    );
+    assert!(Opt::try_parse_from(&["test", "--host", "main"]).is_err());
+}
+
+#[test]
+fn flatten_with_prefix_env() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "replica-")]
+        replica: DbArgs,
+    }
+
+    std::env::set_var("REPLICA_FLATTEN_PREFIX_DB_USER", "reader");
+    assert_eq!(
+        Some("reader".to_string()),
+        Opt::parse_from(&["test", "--replica-host", "backup"])
+            .replica
+            .user
+    );
+}
+
+#[test]
+fn flatten_with_nested_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Cluster {
+        #[clap(long)]
+        name: String,
+        #[clap(flatten)]
+        db: DbArgs,
+        #[clap(flatten, prefix = "cache-")]
+        cache: DbArgs,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "eu-")]
+        eu: Cluster,
+    }
+
+    let mut opt = Opt::parse_from(&[
+        "test",
+        "--eu-name",
+        "west",
+        "--eu-host",
+        "db",
+        "--eu-cache-host",
+        "redis",
+    ]);
+    assert_eq!(
+        Cluster {
+            name: "west".into(),
+            db: DbArgs {
+                host: "db".into(),
+                port: 5432,
+                user: None,
+            },
+            cache: DbArgs {
+                host: "redis".into(),
+                port: 5432,
+                user: None,
+            },
+        },
+        opt.eu
+    );
+
+    opt.update_from(&[
+        "test",
+        "--eu-name",
+        "east",
+        "--eu-host",
+        "db",
+        "--eu-cache-host",
+        "redis",
+        "--eu-cache-port",
+        "6379",
+    ]);
+    assert_eq!("east", opt.eu.name);
+    assert_eq!(6379, opt.eu.cache.port);
+    assert_eq!(5432, opt.eu.db.port);
+}
+
+#[test]
+fn flatten_with_prefix_in_subcommand() {
+    #[derive(Clap, PartialEq, Debug)]
+    enum Opt {
+        Sync {
+            #[clap(flatten, prefix = "from-")]
+            from: DbArgs,
+            #[clap(flatten, prefix = "to-")]
+            to: DbArgs,
+        },
+    }
+
+    assert_eq!(
+        Opt::Sync {
+            from: DbArgs {
+                host: "a".into(),
+                port: 5432,
+                user: None,
+            },
+            to: DbArgs {
+                host: "b".into(),
+                port: 5432,
+                user: None,
+            },
+        },
+        Opt::parse_from(&["test", "sync", "--from-host", "a", "--to-host", "b"])
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct TlsArgs {
+    #[clap(long)]
+    cert: String,
+    #[clap(long)]
+    key: String,
+    #[clap(long, default_value = "1.2")]
+    tls_version: String,
+}
+
+#[test]
+fn flatten_with_prefix_relations() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Auth {
+        #[clap(long, requires = "password")]
+        user: Option<String>,
+        #[clap(long)]
+        password: Option<String>,
+        #[clap(long, conflicts_with_all = &["user", "password"])]
+        anonymous: bool,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "proxy-")]
+        proxy: Auth,
+    }
+
+    assert_eq!(
+        Auth {
+            user: Some("me".into()),
+            password: Some("secret".into()),
+            anonymous: false,
+        },
+        Opt::parse_from(&["test", "--proxy-user", "me", "--proxy-password", "secret"]).proxy
+    );
+    assert!(Opt::try_parse_from(&["test", "--proxy-user", "me"]).is_err());
+    assert!(
+        Opt::try_parse_from(&["test", "--proxy-anonymous", "--proxy-password", "secret"]).is_err()
+    );
+}
+
+#[test]
+fn flatten_option() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        port: Option<u16>,
+        #[clap(flatten)]
+        tls: Option<TlsArgs>,
+    }
+
+    assert_eq!(
+        Opt {
+            port: Some(80),
+            tls: None
+        },
+        Opt::parse_from(&["test", "--port", "80"])
+    );
+    assert_eq!(
+        Opt {
+            port: None,
+            tls: Some(TlsArgs {
+                cert: "cert.pem".into(),
+                key: "key.pem".into(),
+                tls_version: "1.2".into(),
+            }),
+        },
+        Opt::parse_from(&["test", "--cert", "cert.pem", "--key", "key.pem"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--cert", "cert.pem"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--tls-version", "1.3"]).is_err());
+}
+
+#[test]
+fn flatten_option_present_from_command_line_only() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "FLATTEN_OPTION_")]
+    struct Opt {
+        #[clap(flatten)]
+        tls: Option<TlsArgs>,
+    }
+
+    std::env::set_var("FLATTEN_OPTION_CERT", "env-cert.pem");
+    std::env::set_var("FLATTEN_OPTION_KEY", "env-key.pem");
+
+    // the environment variables and default values don't count
+    let mut opt = Opt::parse_from(&["test"]);
+    assert_eq!(Opt { tls: None }, opt);
+    opt.update_from(&["test"]);
+    assert_eq!(Opt { tls: None }, opt);
+
+    // but fill the struct once one of its arguments is given
+    assert_eq!(
+        Opt {
+            tls: Some(TlsArgs {
+                cert: "cert.pem".into(),
+                key: "env-key.pem".into(),
+                tls_version: "1.2".into(),
+            }),
+        },
+        Opt::parse_from(&["test", "--cert", "cert.pem"])
+    );
+}
+
+#[test]
+fn flatten_option_with_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Listener {
+        #[clap(long)]
+        port: u16,
+        #[clap(flatten)]
+        tls: Option<TlsArgs>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "admin-")]
+        admin: Option<Listener>,
+    }
+
+    assert_eq!(Opt { admin: None }, Opt::parse_from(&["test"]));
+    assert_eq!(
+        Opt {
+            admin: Some(Listener {
+                port: 8080,
+                tls: None,
+            }),
+        },
+        Opt::parse_from(&["test", "--admin-port", "8080"])
+    );
+    assert_eq!(
+        Opt {
+            admin: Some(Listener {
+                port: 8443,
+                tls: Some(TlsArgs {
+                    cert: "cert.pem".into(),
+                    key: "key.pem".into(),
+                    tls_version: "1.2".into(),
+                }),
+            }),
+        },
+        Opt::parse_from(&[
+            "test",
+            "--admin-port",
+            "8443",
+            "--admin-cert",
+            "cert.pem",
+            "--admin-key",
+            "key.pem",
+        ])
+    );
+    assert!(
+        Opt::try_parse_from(&["test", "--admin-cert", "cert.pem", "--admin-key", "k"]).is_err()
+    );
+}
+
+#[test]
+fn update_flatten_option() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten)]
+        tls: Option<TlsArgs>,
+    }
+
+    let mut opt = Opt::parse_from(&["test"]);
+    opt.update_from(&["test"]);
+    assert_eq!(None, opt.tls);
+
+    opt.update_from(&["test", "--cert", "a", "--key", "b"]);
+    opt.update_from(&["test", "--cert", "c", "--key", "b"]);
+    assert_eq!(
+        Some(TlsArgs {
+            cert: "c".into(),
+            key: "b".into(),
+            tls_version: "1.2".into(),
+        }),
+        opt.tls
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct InputSpec {
+    #[clap(long)]
+    input: String,
+    #[clap(long, default_value = "csv")]
+    format: String,
+    #[clap(long)]
+    header: bool,
+}
+
+#[test]
+fn flatten_repeated() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, long)]
+        verbose: bool,
+        #[clap(flatten, repeated)]
+        inputs: Vec<InputSpec>,
+    }
+
+    assert_eq!(
+        Opt {
+            verbose: false,
+            inputs: vec![],
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            verbose: true,
+            inputs: vec![
+                InputSpec {
+                    input: "a.csv".into(),
+                    format: "csv".into(),
+                    header: true,
+                },
+                InputSpec {
+                    input: "b.json".into(),
+                    format: "json".into(),
+                    header: false,
+                },
+            ],
+        },
+        Opt::parse_from(&[
+            "test", "--input", "a.csv", "--header", "-v", "--input", "b.json", "--format", "json",
+        ])
+    );
+}
+
+#[test]
+fn flatten_repeated_errors() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, repeated)]
+        inputs: Vec<InputSpec>,
+    }
+
+    let err = Opt::try_parse_from(&["test", "--format", "json", "--input", "a.csv"]).unwrap_err();
+    assert_eq!(clap::ErrorKind::ArgumentConflict, err.kind);
+    assert!(err.message.contains("'format'"));
+
+    let err = Opt::try_parse_from(&[
+        "test", "--input", "a.csv", "--format", "json", "--format", "csv",
+    ])
+    .unwrap_err();
+    assert_eq!(clap::ErrorKind::UnexpectedMultipleUsage, err.kind);
+}
+
+#[test]
+fn flatten_repeated_with_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, repeated, prefix = "out-")]
+        outputs: Vec<InputSpec>,
+    }
+
+    let mut opt = Opt::parse_from(&["test", "--out-input", "a", "--out-input", "b"]);
+    assert_eq!(
+        vec!["a", "b"],
+        opt.outputs.iter().map(|o| &*o.input).collect::<Vec<_>>()
+    );
+
+    opt.update_from(&["test"]);
+    assert_eq!(2, opt.outputs.len());
+    opt.update_from(&["test", "--out-input", "c", "--out-format", "json"]);
+    assert_eq!(
+        vec![InputSpec {
+            input: "c".into(),
+            format: "json".into(),
+            header: false,
+        }],
+        opt.outputs
+    );
+}
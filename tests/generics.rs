@@ -0,0 +1,141 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+#[test]
+fn generic_struct() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt<T> {
+        #[clap(long)]
+        value: T,
+    }
+
+    assert_eq!(
+        Opt { value: 42u32 },
+        Opt::<u32>::parse_from(&["test", "--value", "42"])
+    );
+    assert!(Opt::<u32>::try_parse_from(&["test", "--value", "foo"]).is_err());
+    assert_eq!(
+        Opt {
+            value: "foo".to_string()
+        },
+        Opt::<String>::parse_from(&["test", "--value", "foo"])
+    );
+}
+
+#[test]
+fn generic_struct_with_bounds() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt<T: FromStr + Debug, U>
+    where
+        U: Default,
+    {
+        #[clap(short)]
+        values: Vec<T>,
+        #[clap(short)]
+        optional: Option<T>,
+        #[clap(skip)]
+        unparsed: U,
+    }
+
+    assert_eq!(
+        Opt {
+            values: vec![1, 2],
+            optional: None,
+            unparsed: String::new(),
+        },
+        Opt::<i32, String>::parse_from(&["test", "-v", "1", "-v", "2"])
+    );
+}
+
+#[test]
+fn generic_struct_with_lifetime() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt<'a> {
+        name: String,
+        #[clap(skip = "default")]
+        label: &'a str,
+    }
+
+    assert_eq!(
+        Opt {
+            name: "foo".to_string(),
+            label: "default"
+        },
+        Opt::parse_from(&["test", "foo"])
+    );
+}
+
+#[test]
+fn generic_struct_with_lifetime_b() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt<'b> {
+        name: String,
+        #[clap(skip = "default")]
+        label: &'b str,
+    }
+
+    assert_eq!(
+        Opt {
+            name: "foo".to_string(),
+            label: "default"
+        },
+        Opt::parse_from(&["test", "foo"])
+    );
+}
+
+#[test]
+fn generic_flatten() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Backend<T> {
+        #[clap(long)]
+        port: T,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        name: String,
+        #[clap(flatten)]
+        backend: Backend<u16>,
+    }
+
+    assert_eq!(
+        Opt {
+            name: "db".to_string(),
+            backend: Backend { port: 8080 }
+        },
+        Opt::parse_from(&["test", "--name", "db", "--port", "8080"])
+    );
+}
+
+#[test]
+fn generic_enum() {
+    #[derive(Clap, PartialEq, Debug)]
+    enum Opt<T> {
+        Add { value: T },
+        Remove(#[clap(long)] T, String),
+        List,
+    }
+
+    assert_eq!(
+        Opt::Add { value: 3u8 },
+        Opt::<u8>::parse_from(&["test", "add", "3"])
+    );
+    assert_eq!(
+        Opt::Remove(3u8, "foo".to_string()),
+        Opt::<u8>::parse_from(&["test", "remove", "--remove-0", "3", "foo"])
+    );
+    assert_eq!(Opt::List, Opt::<u8>::parse_from(&["test", "list"]));
+    assert!(Opt::<u8>::try_parse_from(&["test", "add", "300"]).is_err());
+}
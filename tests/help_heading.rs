@@ -0,0 +1,173 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{Clap, IntoApp};
+
+fn heading_of<T: IntoApp>(name: &str) -> Option<String> {
+    let app = T::into_app();
+    let arg = app
+        .get_arguments()
+        .find(|arg| arg.get_name() == name)
+        .unwrap_or_else(|| panic!("no argument `{}`", name));
+    arg.get_help_heading().map(String::from)
+}
+
+#[test]
+fn field_heading() {
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(long, help_heading = "NETWORK")]
+        host: String,
+        #[clap(long, negatable, help_heading = "NETWORK")]
+        tls: bool,
+        #[clap(long)]
+        verbose: bool,
+    }
+
+    assert_eq!(Some("NETWORK".into()), heading_of::<Opt>("host"));
+    assert_eq!(Some("NETWORK".into()), heading_of::<Opt>("tls"));
+    assert_eq!(Some("NETWORK".into()), heading_of::<Opt>("no-tls"));
+    assert_eq!(None, heading_of::<Opt>("verbose"));
+}
+
+#[test]
+fn struct_heading() {
+    #[derive(Clap, Debug)]
+    #[clap(help_heading = "OUTPUT")]
+    struct Opt {
+        #[clap(long)]
+        color: bool,
+        #[clap(long, help_heading = "INPUT")]
+        file: String,
+    }
+
+    assert_eq!(Some("OUTPUT".into()), heading_of::<Opt>("color"));
+    assert_eq!(Some("INPUT".into()), heading_of::<Opt>("file"));
+}
+
+#[test]
+fn flatten_heading() {
+    #[derive(Clap, Debug)]
+    struct Network {
+        #[clap(long)]
+        port: u16,
+        #[clap(long, help_heading = "SECURITY")]
+        tls: bool,
+    }
+
+    #[derive(Clap, Debug)]
+    #[clap(help_heading = "LOGGING")]
+    struct Logging {
+        #[clap(long)]
+        log_level: String,
+    }
+
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(flatten, help_heading = "NETWORK")]
+        network: Network,
+        #[clap(flatten)]
+        logging: Logging,
+        #[clap(long)]
+        verbose: bool,
+    }
+
+    assert_eq!(Some("NETWORK".into()), heading_of::<Opt>("port"));
+    assert_eq!(Some("SECURITY".into()), heading_of::<Opt>("tls"));
+    assert_eq!(Some("LOGGING".into()), heading_of::<Opt>("log-level"));
+    assert_eq!(None, heading_of::<Opt>("verbose"));
+}
+
+#[test]
+fn flatten_doc_comment_heading() {
+    #[derive(Clap, Debug)]
+    struct Logging {
+        #[clap(long)]
+        log_level: String,
+    }
+
+    #[derive(Clap, Debug)]
+    struct Opt {
+        /// Logging options.
+        #[clap(flatten, help_heading)]
+        logging: Logging,
+        /// Ignored.
+        #[clap(flatten)]
+        other: Other,
+    }
+
+    #[derive(Clap, Debug)]
+    struct Other {
+        #[clap(long)]
+        color: bool,
+    }
+
+    assert_eq!(
+        Some("Logging options".into()),
+        heading_of::<Opt>("log-level")
+    );
+    assert_eq!(None, heading_of::<Opt>("color"));
+}
+
+#[test]
+fn struct_heading_over_flatten_heading() {
+    #[derive(Clap, Debug)]
+    #[clap(help_heading = "LOGGING")]
+    struct Logging {
+        #[clap(long)]
+        log_level: String,
+    }
+
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(flatten, help_heading = "OTHER")]
+        logging: Logging,
+    }
+
+    assert_eq!(Some("LOGGING".into()), heading_of::<Opt>("log-level"));
+}
+
+#[test]
+fn variant_heading() {
+    #[derive(Clap, Debug)]
+    struct Remote {
+        #[clap(long)]
+        url: String,
+    }
+
+    #[derive(Clap, Debug)]
+    enum Opt {
+        #[clap(help_heading = "FETCH")]
+        Fetch {
+            #[clap(long)]
+            all: bool,
+            #[clap(long, help_heading = "OUTPUT")]
+            quiet: bool,
+        },
+        #[clap(help_heading = "PUSH")]
+        Push(Remote),
+    }
+
+    let app = Opt::into_app();
+    let heading = |subcommand: &str, name: &str| {
+        app.get_subcommands()
+            .find(|app| app.get_name() == subcommand)
+            .unwrap()
+            .get_arguments()
+            .find(|arg| arg.get_name() == name)
+            .unwrap()
+            .get_help_heading()
+            .map(String::from)
+    };
+
+    assert_eq!(Some("FETCH".into()), heading("fetch", "all"));
+    assert_eq!(Some("OUTPUT".into()), heading("fetch", "quiet"));
+    assert_eq!(Some("PUSH".into()), heading("push", "url"));
+}
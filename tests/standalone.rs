@@ -0,0 +1,84 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, ArgMatches, Error, ErrorKind, FromArgMatches, IntoApp};
+
+#[test]
+fn into_app_for_enum() {
+    #[derive(IntoApp, Debug, PartialEq)]
+    #[clap(rename_all = "screaming_snake")]
+    enum Opt {
+        Add { name: String },
+        Remove,
+    }
+
+    impl FromArgMatches for Opt {
+        fn from_argmatches(matches: &ArgMatches) -> Self {
+            Self::try_from_argmatches(matches).unwrap_or_else(|e| e.exit())
+        }
+
+        fn try_from_argmatches(matches: &ArgMatches) -> Result<Self, Error> {
+            match matches.subcommand() {
+                ("ADD", Some(sub)) => Ok(Opt::Add {
+                    name: sub.value_of("NAME").unwrap().to_string(),
+                }),
+                ("REMOVE", _) => Ok(Opt::Remove),
+                _ => Err(Error::with_description(
+                    "unknown subcommand",
+                    ErrorKind::UnrecognizedSubcommand,
+                )),
+            }
+        }
+    }
+
+    let matches = Opt::into_app()
+        .try_get_matches_from(&["test", "ADD", "foo"])
+        .unwrap();
+    assert_eq!(
+        Opt::Add {
+            name: "foo".to_string()
+        },
+        Opt::from_argmatches(&matches)
+    );
+    assert!(Opt::into_app()
+        .try_get_matches_from(&["test", "add", "foo"])
+        .is_err());
+}
+
+#[test]
+fn from_argmatches_for_enum() {
+    #[derive(FromArgMatches, Debug, PartialEq)]
+    #[clap(rename_all = "screaming_snake")]
+    enum Opt {
+        Add { name: String },
+        Remove,
+    }
+
+    impl IntoApp for Opt {
+        fn into_app<'b>() -> App<'b> {
+            App::new("test")
+                .subcommand(App::new("ADD").arg(clap::Arg::with_name("NAME").required(true)))
+                .subcommand(App::new("REMOVE"))
+        }
+    }
+
+    let matches = Opt::into_app()
+        .try_get_matches_from(&["test", "ADD", "foo"])
+        .unwrap();
+    assert_eq!(
+        Opt::Add {
+            name: "foo".to_string()
+        },
+        Opt::from_argmatches(&matches)
+    );
+
+    let matches = Opt::into_app().try_get_matches_from(&["test"]).unwrap();
+    assert!(Opt::try_from_argmatches(&matches).is_err());
+}
@@ -207,3 +207,95 @@ fn flatten_enum() {
         }
     );
 }
+
+#[test]
+fn external_subcommand() {
+    #[derive(Debug, PartialEq, Clap)]
+    struct Opt {
+        #[clap(subcommand)]
+        sub: Subcommands,
+    }
+
+    #[derive(Debug, PartialEq, Clap)]
+    enum Subcommands {
+        Add,
+        Remove,
+        #[clap(external_subcommand)]
+        Other(Vec<String>),
+    }
+
+    assert_eq!(
+        Opt::parse_from(&["test", "add"]),
+        Opt {
+            sub: Subcommands::Add
+        }
+    );
+
+    assert_eq!(
+        Opt::parse_from(&["test", "git", "status", "--short"]),
+        Opt {
+            sub: Subcommands::Other(vec![
+                String::from("git"),
+                String::from("status"),
+                String::from("--short")
+            ])
+        }
+    );
+
+    assert_eq!(
+        Opt::parse_from(&["test", "git"]),
+        Opt {
+            sub: Subcommands::Other(vec![String::from("git")])
+        }
+    );
+
+    assert!(Opt::try_parse_from(&["test"]).is_err());
+}
+
+#[test]
+fn external_subcommand_os_string() {
+    use std::ffi::OsString;
+
+    #[derive(Debug, PartialEq, Clap)]
+    struct Opt {
+        #[clap(subcommand)]
+        sub: Subcommands,
+    }
+
+    #[derive(Debug, PartialEq, Clap)]
+    enum Subcommands {
+        #[clap(external_subcommand)]
+        Other(Vec<OsString>),
+    }
+
+    assert_eq!(
+        Opt::parse_from(&["test", "git", "status"]),
+        Opt {
+            sub: Subcommands::Other(vec![OsString::from("git"), OsString::from("status")])
+        }
+    );
+}
+
+#[test]
+fn external_subcommand_optional() {
+    #[derive(Debug, PartialEq, Clap)]
+    struct Opt {
+        #[clap(subcommand)]
+        sub: Option<Subcommands>,
+    }
+
+    #[derive(Debug, PartialEq, Clap)]
+    enum Subcommands {
+        #[clap(external_subcommand)]
+        Other(Vec<String>),
+    }
+
+    assert_eq!(
+        Opt::parse_from(&["test", "run"]),
+        Opt {
+            sub: Some(Subcommands::Other(vec![String::from("run")]))
+        }
+    );
+
+    assert_eq!(Opt::parse_from(&["test"]), Opt { sub: None });
+}
@@ -0,0 +1,92 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{App, Arg, Clap, ErrorKind, FromArgMatches};
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long)]
+    num: u32,
+    #[clap(long)]
+    nums: Vec<u32>,
+}
+
+fn unvalidated_app() -> App<'static> {
+    App::new("test")
+        .arg(Arg::with_name("num").long("num").takes_value(true))
+        .arg(
+            Arg::with_name("nums")
+                .long("nums")
+                .takes_value(true)
+                .multiple(true),
+        )
+}
+
+#[test]
+fn valid_values() {
+    let matches = unvalidated_app()
+        .try_get_matches_from(&["test", "--num", "1", "--nums", "2", "3"])
+        .unwrap();
+    assert_eq!(
+        Opt {
+            num: 1,
+            nums: vec![2, 3],
+        },
+        Opt::try_from_argmatches(&matches).unwrap()
+    );
+}
+
+#[test]
+fn invalid_value_reports_the_argument() {
+    let matches = unvalidated_app()
+        .try_get_matches_from(&["test", "--num", "1", "--nums", "2", "x"])
+        .unwrap();
+    let err = Opt::try_from_argmatches(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(err.message.contains("'nums'"));
+    assert!(err.message.contains("invalid digit found in string"));
+}
+
+#[test]
+fn missing_required_value() {
+    let matches = unvalidated_app()
+        .try_get_matches_from(&["test"])
+        .unwrap();
+    let err = Opt::try_from_argmatches(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+    assert!(err.message.contains("'num'"));
+}
+
+#[test]
+fn missing_subcommand() {
+    #[derive(Clap, PartialEq, Debug)]
+    enum Cmd {
+        Fetch,
+        Add { name: String },
+    }
+
+    let matches = App::new("test").try_get_matches_from(&["test"]).unwrap();
+    let err = Cmd::try_from_argmatches(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingSubcommand);
+
+    let matches = App::new("test")
+        .subcommand(App::new("add").arg(Arg::with_name("name")))
+        .try_get_matches_from(&["test", "add"])
+        .unwrap();
+    let err = Cmd::try_from_argmatches(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+}
+
+#[test]
+fn try_parse_from_validates() {
+    let err = Opt::try_parse_from(&["test", "--num", "x"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(Opt::try_parse_from(&["test", "--num", "1"]).is_ok());
+}
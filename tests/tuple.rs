@@ -0,0 +1,89 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use clap::Clap;
+use std::path::PathBuf;
+use utils::*;
+
+#[test]
+fn tuple_struct() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt(String, u32);
+
+    assert_eq!(
+        Opt("foo".to_string(), 42),
+        Opt::parse_from(&["test", "foo", "42"])
+    );
+    assert!(Opt::try_parse_from(&["test", "foo"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "foo", "bar"]).is_err());
+}
+
+#[test]
+fn tuple_struct_with_attributes() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt(
+        #[clap(short, long = "verbose")] bool,
+        #[clap(name = "level", long)] Option<u8>,
+        #[clap(parse(from_os_str))] PathBuf,
+    );
+
+    assert_eq!(
+        Opt(true, Some(3), PathBuf::from("file")),
+        Opt::parse_from(&["test", "-o", "--level", "3", "file"])
+    );
+    assert_eq!(
+        Opt(true, None, PathBuf::from("file")),
+        Opt::parse_from(&["test", "file", "--verbose"])
+    );
+}
+
+#[test]
+fn tuple_struct_positional_names() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt(String, #[clap(name = "count")] u32);
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("<opt-0>"));
+    assert!(help.contains("<count>"));
+}
+
+#[test]
+fn tuple_variant_with_multiple_fields() {
+    #[derive(Clap, PartialEq, Debug)]
+    enum Opt {
+        /// Copy a file
+        Copy(#[clap(parse(from_os_str))] PathBuf, #[clap(parse(from_os_str))] PathBuf),
+        Move(
+            #[clap(short, long = "force")] bool,
+            #[clap(parse(from_os_str))] PathBuf,
+            #[clap(parse(from_os_str))] PathBuf,
+        ),
+    }
+
+    assert_eq!(
+        Opt::Copy(PathBuf::from("a"), PathBuf::from("b")),
+        Opt::parse_from(&["test", "copy", "a", "b"])
+    );
+    assert_eq!(
+        Opt::Move(true, PathBuf::from("a"), PathBuf::from("b")),
+        Opt::parse_from(&["test", "move", "a", "--force", "b"])
+    );
+    assert_eq!(
+        Opt::Move(false, PathBuf::from("a"), PathBuf::from("b")),
+        Opt::parse_from(&["test", "move", "a", "b"])
+    );
+    assert!(Opt::try_parse_from(&["test", "copy", "a"]).is_err());
+
+    let help = get_subcommand_long_help::<Opt>("copy");
+    assert!(help.contains("<copy-0>"));
+    assert!(help.contains("<copy-1>"));
+}
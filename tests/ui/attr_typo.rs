@@ -0,0 +1,29 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Common {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic", afterhelp = "Some more help")]
+struct Opt {
+    #[clap(short, defualt_value = "1")]
+    count: u32,
+    #[clap(flaten)]
+    common: Common,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
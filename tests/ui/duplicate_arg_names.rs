@@ -0,0 +1,33 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+struct Opt {
+    #[clap(short)]
+    verbose: bool,
+    #[clap(short, long)]
+    version: bool,
+    #[clap(long = "input")]
+    file: String,
+    #[clap(long)]
+    input: String,
+    #[clap(name = "level", long)]
+    debug_level: u32,
+    #[clap(long = "log-level")]
+    level: u32,
+    #[clap(short)]
+    value: u32,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
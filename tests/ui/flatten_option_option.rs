@@ -9,19 +9,16 @@
 use clap::Clap;
 
 #[derive(Clap, Debug)]
-struct DaemonOpts {
-    #[clap(short)]
-    user: String,
-    #[clap(short)]
-    group: String,
+struct TlsArgs {
+    #[clap(long)]
+    cert: String,
 }
 
 #[derive(Clap, Debug)]
 #[clap(name = "basic")]
 struct Opt {
-    /// Opts.
     #[clap(flatten)]
-    opts: DaemonOpts,
+    tls: Option<Option<TlsArgs>>,
 }
 
 fn main() {
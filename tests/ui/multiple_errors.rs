@@ -0,0 +1,33 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Common {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic", rename_all = "unknown", no_version, version = "1.0")]
+struct Opt {
+    #[clap(short, unknown_attr, default_value = "1")]
+    debug: bool,
+    #[clap(long, parse(from_nothing))]
+    name: String,
+    #[clap(flatten, long)]
+    common: Common,
+    #[clap(long, prefix = "foo-")]
+    count: u32,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,37 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+enum Opt {
+    #[clap(flatten)]
+    First,
+    Second {
+        #[clap(short, required = true)]
+        name: Option<String>,
+        #[clap(subcommand)]
+        sub: Sub,
+        #[clap(subcommand)]
+        other: Sub,
+    },
+    #[clap(external_subcommand)]
+    Other(Vec<String>),
+    #[clap(external_subcommand)]
+    Another(Vec<String>),
+}
+
+#[derive(Clap, Debug)]
+enum Sub {
+    Run,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,23 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+enum Command {
+    #[clap(external_subcommand)]
+    Run(Vec<String>),
+
+    #[clap(external_subcommand)]
+    Other(Vec<String>),
+}
+
+fn main() {
+    let cmd = Command::parse();
+    println!("{:?}", cmd);
+}
@@ -10,7 +10,10 @@ use clap::Clap;
 
 #[derive(Clap, Debug)]
 #[clap(name = "basic")]
-struct Opt(u32);
+struct Opt {
+    #[clap(long, negatable)]
+    level: u32,
+}
 
 fn main() {
     let opt = Opt::parse();
@@ -0,0 +1,237 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{Clap, FromArgMatches, IntoApp};
+
+#[derive(Clap, PartialEq, Debug)]
+struct Common {
+    #[clap(short, long)]
+    verbose: bool,
+    #[clap(long)]
+    color: Option<String>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(short, long, default_value = "42")]
+    count: u32,
+    #[clap(long)]
+    name: Option<String>,
+    #[clap(flatten)]
+    common: Common,
+}
+
+#[test]
+fn update_keeps_absent_arguments() {
+    let mut opt = Opt::parse_from(&["test", "--count", "1", "--name", "first"]);
+
+    opt.update_from(&["test", "--color", "red"]);
+    assert_eq!(
+        Opt {
+            count: 1,
+            name: Some("first".into()),
+            common: Common {
+                verbose: false,
+                color: Some("red".into()),
+            },
+        },
+        opt
+    );
+
+    opt.update_from(&["test", "-v", "--count", "3"]);
+    assert_eq!(
+        Opt {
+            count: 3,
+            name: Some("first".into()),
+            common: Common {
+                verbose: true,
+                color: Some("red".into()),
+            },
+        },
+        opt
+    );
+}
+
+#[test]
+fn update_from_argmatches() {
+    let mut opt = Opt::parse_from(&["test", "--name", "first"]);
+    let matches = Opt::into_app().get_matches_from(&["test", "--count", "7"]);
+    opt.update_from_argmatches(&matches);
+    assert_eq!(7, opt.count);
+    assert_eq!(Some("first".into()), opt.name);
+}
+
+#[test]
+fn try_update_reports_errors() {
+    let mut opt = Opt::parse_from(&["test", "--count", "1"]);
+    assert!(opt.try_update_from(&["test", "--count", "nope"]).is_err());
+    assert_eq!(1, opt.count);
+}
+
+#[test]
+fn update_takes_environment_values() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "UPDATE_ENV_")]
+    struct Opt {
+        #[clap(long, default_value = "1")]
+        jobs: u32,
+        #[clap(long, env = ["UPDATE_ENV_NEW_TOKEN", "UPDATE_ENV_OLD_TOKEN"])]
+        token: Option<String>,
+        #[clap(env_only)]
+        region: Option<String>,
+    }
+
+    let mut opt = Opt::parse_from(&["test", "--jobs", "4"]);
+    opt.update_from(&["test"]);
+    assert_eq!(
+        Opt {
+            jobs: 4,
+            token: None,
+            region: None,
+        },
+        opt
+    );
+
+    std::env::set_var("UPDATE_ENV_JOBS", "8");
+    std::env::set_var("UPDATE_ENV_OLD_TOKEN", "old");
+    std::env::set_var("UPDATE_ENV_REGION", "eu");
+    opt.update_from(&["test"]);
+    assert_eq!(
+        Opt {
+            jobs: 8,
+            token: Some("old".into()),
+            region: Some("eu".into()),
+        },
+        opt
+    );
+
+    opt.update_from(&["test", "--jobs", "2"]);
+    assert_eq!(2, opt.jobs);
+}
+
+#[test]
+fn update_takes_environment_values_over_default_value_t() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(env_prefix = "UPDATE_DEFAULT_ENV_")]
+    struct Opt {
+        #[clap(long, default_value_t = 1)]
+        jobs: u32,
+    }
+
+    let mut opt = Opt::parse_from(&["test", "--jobs", "4"]);
+    opt.update_from(&["test"]);
+    assert_eq!(Opt { jobs: 4 }, opt);
+
+    std::env::set_var("UPDATE_DEFAULT_ENV_JOBS", "8");
+    opt.update_from(&["test"]);
+    assert_eq!(Opt { jobs: 8 }, opt);
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Fetch {
+    #[clap(long)]
+    all: bool,
+    remote: Option<String>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    Add {
+        #[clap(short)]
+        interactive: bool,
+        path: Option<String>,
+    },
+    Fetch(Fetch),
+    Init,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Git {
+    #[clap(long)]
+    dry_run: bool,
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[test]
+fn update_same_subcommand() {
+    let mut git = Git::parse_from(&["test", "add", "-i"]);
+    git.update_from(&["test", "add", "file"]);
+    assert_eq!(
+        Git {
+            dry_run: false,
+            cmd: Command::Add {
+                interactive: true,
+                path: Some("file".into()),
+            },
+        },
+        git
+    );
+
+    let mut git = Git::parse_from(&["test", "fetch", "origin"]);
+    git.update_from(&["test", "--dry-run", "fetch", "--all"]);
+    assert_eq!(
+        Git {
+            dry_run: true,
+            cmd: Command::Fetch(Fetch {
+                all: true,
+                remote: Some("origin".into()),
+            }),
+        },
+        git
+    );
+}
+
+#[test]
+fn update_switches_subcommand() {
+    let mut git = Git::parse_from(&["test", "add", "-i"]);
+    git.update_from(&["test", "fetch"]);
+    assert_eq!(
+        Git {
+            dry_run: false,
+            cmd: Command::Fetch(Fetch {
+                all: false,
+                remote: None,
+            }),
+        },
+        git
+    );
+
+    git.update_from(&["test", "init"]);
+    assert_eq!(Command::Init, git.cmd);
+}
+
+#[test]
+fn update_optional_subcommand() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(subcommand)]
+        cmd: Option<Command>,
+    }
+
+    let mut opt = Opt::parse_from(&["test"]);
+    opt.update_from(&["test", "add", "file"]);
+    assert_eq!(
+        Some(Command::Add {
+            interactive: false,
+            path: Some("file".into()),
+        }),
+        opt.cmd
+    );
+
+    opt.update_from(&["test", "add", "-i"]);
+    assert_eq!(
+        Some(Command::Add {
+            interactive: true,
+            path: Some("file".into()),
+        }),
+        opt.cmd
+    );
+}